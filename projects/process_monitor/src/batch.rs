@@ -0,0 +1,106 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::{thread, time::Duration};
+
+use process_monitor::{Snapshot, System};
+
+use crate::config::{Config, OutputFormat};
+
+// one snapshot of the view chosen on the command line
+fn write_snapshot<W: Write>(sys: &System, config: &Config, out: &mut W) -> io::Result<()> {
+    if config.threads {
+        sys.write_threads(out, config.limit)
+    } else if config.cgroups {
+        sys.write_cgroups(out, config.limit)
+    } else if config.listening {
+        sys.write_listening(out, config.limit)
+    } else if config.tree {
+        sys.write_tree(out, config.limit)
+    } else {
+        sys.write_processes(out, config.limit)
+    }
+}
+
+/// Writes `config.iterations` snapshots to `out`, or snapshots until stopped,
+/// `config.delay_millis` apart and separated by a blank line, like `top -b`.
+pub fn run<W: Write>(mut sys: System, config: &Config, out: &mut W) -> Result<(), Box<dyn Error>> {
+    let mut iteration: u64 = 0;
+    loop {
+        sys.refresh()?;
+        match config.format {
+            OutputFormat::Text => {
+                if iteration > 0 {
+                    writeln!(out)?;
+                }
+                write_snapshot(&sys, config, out)?;
+            }
+            OutputFormat::Json => writeln!(out, "{}", Snapshot::new(&sys, config.limit).to_json_pretty()?)?,
+            OutputFormat::JsonLines => writeln!(out, "{}", Snapshot::new(&sys, config.limit).to_json_line()?)?,
+        }
+        // a reader like tail -f sees every snapshot as soon as it's complete
+        out.flush()?;
+
+        iteration += 1;
+        if config.iterations.is_some_and(|iterations| iteration >= iterations) {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(config.delay_millis));
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::*;
+    use process_monitor::{FsRoot, Process};
+
+    /// A system read from the fixtures, like `linux_parser::tests::fixture_root`
+    /// which the library only builds for its own tests.
+    pub fn fixture_system() -> System {
+        let root = FsRoot::new(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/proc"),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/etc"),
+        )
+        .with_sys_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/sys"));
+        System::new(root).unwrap()
+    }
+
+    fn run_batch(args: &[&str]) -> String {
+        let args = ["process_monitor", "-b", "-d", "0.001"].iter().chain(args.iter()).map(|arg| arg.to_string());
+        let config = Config::new(args).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        run(fixture_system(), &config, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn snapshots_without_clearing() {
+        let out = run_batch(&["-n", "2", "--limit", "2"]);
+        let head = Process::head_str();
+        let snapshots: Vec<&str> = out.split(&head).skip(1).collect();
+        assert_eq!(snapshots.len(), 2);
+        // the header line ends before the rows, a blank line separates the snapshots
+        assert_eq!(snapshots[0].lines().skip(1).take_while(|line| !line.is_empty()).count(), 2);
+        assert!(!out.contains("\x1b["));
+    }
+
+    #[test]
+    fn json_lines() {
+        let out = run_batch(&["-n", "2", "--limit", "0", "--ndjson"]);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for line in lines {
+            let json: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(json["schema_version"], process_monitor::SCHEMA_VERSION);
+            assert_eq!(json["processes"].as_array().unwrap().len(), 3);
+        }
+    }
+
+    #[test]
+    fn all_rows_of_the_chosen_view() {
+        let out = run_batch(&["-n", "1", "--limit", "0", "--cgroups"]);
+        assert!(out.contains("CGROUP"));
+        assert!(out.contains("/init.scope"));
+        assert!(!out.ends_with("\n\n"));
+    }
+}
@@ -0,0 +1,146 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::linux_parser::{self, CgroupLimits, FsRoot};
+use crate::process::{self, Process, SortKey};
+
+/// The summed usage of the processes in one cgroup, next to the cgroup's limits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CgroupUsage {
+    /// The cgroup v2 path, empty for processes without cgroup v2.
+    pub path: String,
+    pub processes: usize,
+    /// CPU% of one core, like the CPU% of a process.
+    pub cpu_utilization: f64,
+    /// Summed data segments in kB.
+    pub ram: u64,
+    /// Bytes per second, `None` if no process of the cgroup has a readable io file.
+    pub read_rate: Option<f64>,
+    pub write_rate: Option<f64>,
+    pub limits: CgroupLimits,
+}
+
+fn add_rate(sum: Option<f64>, rate: Option<f64>) -> Option<f64> {
+    match (sum, rate) {
+        (Some(sum), Some(rate)) => Some(sum + rate),
+        (sum, rate) => sum.or(rate),
+    }
+}
+
+impl CgroupUsage {
+    fn new(path: &str) -> CgroupUsage {
+        CgroupUsage {
+            path: path.to_string(),
+            processes: 0,
+            cpu_utilization: 0.0,
+            ram: 0,
+            read_rate: None,
+            write_rate: None,
+            limits: CgroupLimits::default(),
+        }
+    }
+
+    fn add(&mut self, process: &Process) {
+        self.processes += 1;
+        self.cpu_utilization += process.cpu_utilization();
+        self.ram += process.ram() as u64;
+        self.read_rate = add_rate(self.read_rate, process.read_rate());
+        self.write_rate = add_rate(self.write_rate, process.write_rate());
+    }
+
+    /// Orders cgroups by `key`, lowest first. Columns a cgroup doesn't have sort by path.
+    pub fn compare(&self, other: &CgroupUsage, key: SortKey) -> Ordering {
+        match key {
+            SortKey::Cpu => self.cpu_utilization.partial_cmp(&other.cpu_utilization).unwrap_or(Ordering::Equal),
+            SortKey::Ram => self.ram.cmp(&other.ram),
+            SortKey::Threads => self.processes.cmp(&other.processes),
+            SortKey::IoRead => self.read_rate.partial_cmp(&other.read_rate).unwrap_or(Ordering::Equal),
+            SortKey::IoWrite => self.write_rate.partial_cmp(&other.write_rate).unwrap_or(Ordering::Equal),
+            _ => self.path.cmp(&other.path),
+        }
+    }
+
+    pub fn head_str() -> String {
+        format!(
+            "{:>6} {:<8} {:>8} {:<10} {:>10} {:>8} {:>8} {:<40}",
+            "PROCS",
+            "CPU[%]",
+            "LIMIT",
+            "RAM[KB]",
+            "LIMIT",
+            "READ/s",
+            "WRITE/s",
+            "CGROUP",
+        )
+    }
+}
+
+impl fmt::Display for CgroupUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // limits in the unit of the usage next to them, - for unlimited
+        let cpu_max = match self.limits.cpu_max {
+            Some(cpus) => format!("{:.0}", cpus * 100.0),
+            None => String::from("-"),
+        };
+        let memory_max = match self.limits.memory_max {
+            Some(bytes) => (bytes / 1024).to_string(),
+            None => String::from("-"),
+        };
+        write!(
+            f, "{:>6} {:<8.2} {:>8} {:<10} {:>10} {:>8} {:>8} {:<40}",
+            self.processes,
+            self.cpu_utilization,
+            cpu_max,
+            self.ram,
+            memory_max,
+            process::format_rate(self.read_rate),
+            process::format_rate(self.write_rate),
+            if self.path.is_empty() { "-" } else { &self.path },
+        )
+    }
+}
+
+/// Sums the usage of `processes` per cgroup and reads the limits of each cgroup.
+/// Limits that can't be read are shown as unlimited.
+pub fn group_by_cgroup(root: &FsRoot, processes: &[&Process]) -> Vec<CgroupUsage> {
+    let mut groups: HashMap<&str, CgroupUsage> = HashMap::new();
+    for process in processes {
+        groups
+            .entry(process.cgroup())
+            .or_insert_with(|| CgroupUsage::new(process.cgroup()))
+            .add(process);
+    }
+    let mut groups: Vec<CgroupUsage> = groups.into_values().collect();
+    for group in groups.iter_mut() {
+        if !group.path.is_empty() {
+            group.limits = linux_parser::get_cgroup_limits(root, &group.path).unwrap_or_default();
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn fixture_groups() {
+        let root = linux_parser::tests::fixture_root();
+        let processes = linux_parser::tests::fixture_processes();
+        let processes: Vec<&Process> = processes.iter().collect();
+        let mut groups = group_by_cgroup(&root, &processes);
+        groups.sort_by(|a, b| a.compare(b, SortKey::Command));
+
+        let paths: Vec<&str> = groups.iter().map(|group| group.path.as_str()).collect();
+        assert_eq!(paths, vec!["/", "/init.scope", linux_parser::tests::WORKER_CGROUP]);
+        let worker = &groups[2];
+        assert_eq!(worker.processes, 1);
+        assert_eq!(worker.ram, 16000);
+        assert_eq!(worker.limits.cpu_max, Some(1.5));
+        assert!(worker.to_string().contains("150"));
+        // pid 1 has no readable io file
+        assert_eq!(groups[1].read_rate, None);
+    }
+}
@@ -0,0 +1,263 @@
+use process_monitor::linux_parser::{self, FsRoot};
+use process_monitor::filter::{self, CommandPattern, ProcessFilter};
+use process_monitor::{SortKey, SortOrder};
+
+/// How batch mode writes each snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    /// One indented JSON document per snapshot.
+    Json,
+    /// One JSON document per line.
+    JsonLines,
+}
+
+const DEFAULT_DELAY_MILLIS: u64 = 2000;
+const DEFAULT_LIMIT: usize = 10;
+
+pub struct Config {
+    pub proc_root: String,
+    pub etc_root: String,
+    pub sys_root: String,
+    pub sort_key: SortKey,
+    pub sort_order: SortOrder,
+    pub filter: ProcessFilter,
+    pub tree: bool,
+    pub threads: bool,
+    pub cgroups: bool,
+    pub listening: bool,
+    pub show_loopback: bool,
+    /// A JSON file of container IDs to names.
+    pub container_names: Option<String>,
+    /// Print plain snapshots instead of the interactive view, also without a terminal.
+    pub batch: bool,
+    /// The number of snapshots of batch mode, `None` to run until stopped.
+    pub iterations: Option<u64>,
+    pub delay_millis: u64,
+    /// The number of rows per snapshot of batch mode, 0 for all.
+    pub limit: usize,
+    /// The file batch mode writes to instead of stdout.
+    pub output: Option<String>,
+    pub format: OutputFormat,
+}
+
+impl Config {
+    pub fn new<I>(mut args: I) -> Result<Config, String>
+    where I: Iterator<Item = String>, {
+        // skip the name of the program
+        args.next();
+
+        let mut proc_root = String::from(linux_parser::PROC_DIR);
+        let mut etc_root = String::from(linux_parser::ETC_DIR);
+        let mut sys_root = String::from(linux_parser::SYS_DIR);
+        let mut sort_key = SortKey::Cpu;
+        let mut sort_order = SortOrder::Descending;
+        let mut filter = ProcessFilter::default();
+        let mut tree = false;
+        let mut threads = false;
+        let mut cgroups = false;
+        let mut listening = false;
+        let mut show_loopback = true;
+        let mut container_names = None;
+        let mut batch = false;
+        let mut iterations = None;
+        let mut delay_millis = DEFAULT_DELAY_MILLIS;
+        let mut limit = DEFAULT_LIMIT;
+        let mut output = None;
+        let mut format = OutputFormat::Text;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--proc-root" => proc_root = flag_value(&arg, args.next())?,
+                "--etc-root" => etc_root = flag_value(&arg, args.next())?,
+                "--sys-root" => sys_root = flag_value(&arg, args.next())?,
+                "--sort" => sort_key = flag_value(&arg, args.next())?.parse()?,
+                "--order" => sort_order = flag_value(&arg, args.next())?.parse()?,
+                "--user" => filter.users.extend(filter::parse_list(&flag_value(&arg, args.next())?)),
+                "--pid" => filter.pids.extend(filter::parse_pids(&flag_value(&arg, args.next())?)?),
+                "--container" => filter.containers.extend(filter::parse_list(&flag_value(&arg, args.next())?)),
+                "--container-names" => container_names = Some(flag_value(&arg, args.next())?),
+                "--tree" => tree = true,
+                "--threads" => threads = true,
+                "--cgroups" => cgroups = true,
+                "--listening" => listening = true,
+                "--hide-loopback" => show_loopback = false,
+                "-b" | "--batch" => batch = true,
+                "-n" | "--iterations" => iterations = Some(parse_iterations(&arg, &flag_value(&arg, args.next())?)?),
+                "-d" | "--delay" => delay_millis = parse_delay(&flag_value(&arg, args.next())?)?,
+                "--limit" => limit = parse_count(&arg, &flag_value(&arg, args.next())?)? as usize,
+                "--json" => format = OutputFormat::Json,
+                "--ndjson" => format = OutputFormat::JsonLines,
+                "-o" | "--output" => output = Some(flag_value(&arg, args.next())?),
+                "--command" => {
+                    filter.command = Some(CommandPattern::Substring(flag_value(&arg, args.next())?))
+                }
+                "--command-regex" => {
+                    filter.command = Some(CommandPattern::regex(&flag_value(&arg, args.next())?)?)
+                }
+                _ => return Err(format!("Unknown argument {}", arg)),
+            }
+        }
+
+        // the JSON snapshot only has the process list
+        if format != OutputFormat::Text {
+            let view = [("--tree", tree), ("--threads", threads), ("--cgroups", cgroups), ("--listening", listening)]
+                .iter()
+                .find(|(_, set)| *set)
+                .map(|(flag, _)| *flag);
+            if let Some(view) = view {
+                return Err(format!("{} can't be combined with --json or --ndjson", view));
+            }
+        }
+
+        Ok(Config {
+            proc_root,
+            etc_root,
+            sys_root,
+            sort_key,
+            sort_order,
+            filter,
+            tree,
+            threads,
+            cgroups,
+            listening,
+            show_loopback,
+            container_names,
+            batch,
+            iterations,
+            delay_millis,
+            limit,
+            output,
+            format,
+        })
+    }
+
+    pub fn fs_root(&self) -> FsRoot {
+        FsRoot::new(&self.proc_root, &self.etc_root).with_sys_dir(&self.sys_root)
+    }
+}
+
+fn flag_value(flag: &str, value: Option<String>) -> Result<String, String> {
+    match value {
+        Some(value) => Ok(value),
+        None => Err(format!("Didn't get a value for {}", flag)),
+    }
+}
+
+fn parse_count(flag: &str, value: &str) -> Result<u64, String> {
+    value.parse().map_err(|_| format!("Invalid value {} for {}", value, flag))
+}
+
+// at least one snapshot, batch mode writes the first one before it checks the count
+fn parse_iterations(flag: &str, value: &str) -> Result<u64, String> {
+    match parse_count(flag, value)? {
+        0 => Err(format!("Invalid value 0 for {}, expected at least 1", flag)),
+        iterations => Ok(iterations),
+    }
+}
+
+// seconds with a fraction like top's -d 0.5, in milliseconds
+fn parse_delay(value: &str) -> Result<u64, String> {
+    match value.parse::<f64>() {
+        Ok(secs) if secs.is_finite() && secs > 0.0 => Ok((secs * 1000.0).round().max(1.0) as u64),
+        _ => Err(format!("Invalid delay {}, expected seconds greater than 0", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Config, String> {
+        let args = std::iter::once("process_monitor")
+            .chain(args.iter().copied())
+            .map(String::from);
+        Config::new(args)
+    }
+
+    #[test]
+    fn default_roots() {
+        let config = parse(&[]).unwrap();
+        assert_eq!(config.proc_root, "/proc/");
+        assert_eq!(config.etc_root, "/etc/");
+    }
+
+    #[test]
+    fn custom_roots() {
+        let config = parse(&["--proc-root", "/host/proc", "--etc-root", "/host/etc", "--sys-root", "/host/sys"]).unwrap();
+        let root = config.fs_root();
+        assert_eq!(root.proc_path("stat"), "/host/proc/stat");
+        assert_eq!(root.pid_path(1, "stat"), "/host/proc/1/stat");
+        assert_eq!(root.etc_path("passwd"), "/host/etc/passwd");
+        assert_eq!(root.sys_path("fs/cgroup"), "/host/sys/fs/cgroup");
+    }
+
+    #[test]
+    fn sort_flags() {
+        let config = parse(&["--sort", "user", "--order", "asc"]).unwrap();
+        assert_eq!(config.sort_key, SortKey::User);
+        assert_eq!(config.sort_order, SortOrder::Ascending);
+        assert!(parse(&["--sort", "size"]).is_err());
+    }
+
+    #[test]
+    fn filter_flags() {
+        let config = parse(&["--user", "root,1000", "--pid", "1", "--pid", "42", "--command", "nginx"]).unwrap();
+        assert_eq!(config.filter.users, vec!["root", "1000"]);
+        assert_eq!(config.filter.pids, vec![1, 42]);
+        assert!(config.filter.command.is_some());
+        assert!(parse(&["--command-regex", "("]).is_err());
+        assert!(parse(&["--pid", "init"]).is_err());
+    }
+
+    #[test]
+    fn container_flags() {
+        let config = parse(&["--container", "web,3f4e1a2b5c6d", "--container-names", "/etc/containers.json"]).unwrap();
+        assert_eq!(config.filter.containers, vec!["web", "3f4e1a2b5c6d"]);
+        assert_eq!(config.container_names.as_deref(), Some("/etc/containers.json"));
+        assert!(parse(&["--container-names"]).is_err());
+    }
+
+    #[test]
+    fn view_flags() {
+        let config = parse(&["--threads"]).unwrap();
+        assert!(config.threads);
+        assert!(!config.tree);
+        assert!(config.show_loopback);
+        assert!(parse(&["--cgroups"]).unwrap().cgroups);
+        assert!(parse(&["--listening"]).unwrap().listening);
+        assert!(!parse(&["--hide-loopback"]).unwrap().show_loopback);
+    }
+
+    #[test]
+    fn batch_flags() {
+        let config = parse(&[]).unwrap();
+        assert!(!config.batch);
+        assert_eq!((config.iterations, config.delay_millis, config.limit), (None, 2000, 10));
+        let config = parse(&["-b", "-n", "3", "-d", "0.5", "--limit", "0", "-o", "/tmp/top.log"]).unwrap();
+        assert!(config.batch);
+        assert_eq!((config.iterations, config.delay_millis, config.limit), (Some(3), 500, 0));
+        assert_eq!(config.output.as_deref(), Some("/tmp/top.log"));
+        assert!(parse(&["--delay", "0"]).is_err());
+        assert!(parse(&["--delay", "inf"]).is_err());
+        assert!(parse(&["--iterations", "-1"]).is_err());
+        assert!(parse(&["-n", "0"]).is_err());
+    }
+
+    #[test]
+    fn json_flags() {
+        assert_eq!(parse(&[]).unwrap().format, OutputFormat::Text);
+        assert_eq!(parse(&["--json"]).unwrap().format, OutputFormat::Json);
+        assert_eq!(parse(&["--ndjson"]).unwrap().format, OutputFormat::JsonLines);
+        assert!(parse(&["--json", "--threads"]).is_err());
+        assert!(parse(&["--cgroups", "--ndjson"]).is_err());
+        assert!(parse(&["--json", "--listening"]).is_err());
+        assert!(parse(&["--ndjson", "--tree"]).is_err());
+    }
+
+    #[test]
+    fn missing_root_value() {
+        assert!(parse(&["--proc-root"]).is_err());
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+
+use crate::error::ParserError;
+
+/// Container IDs are shown cut down to this many characters, like `docker ps` does.
+pub const SHORT_ID_LEN: usize = 12;
+
+const ID_LEN: usize = 64;
+
+// scope and directory name prefixes in front of the container ID:
+// docker and containerd/CRI with the systemd cgroup driver, CRI-O and podman
+const ID_PREFIXES: [&str; 4] = ["docker-", "cri-containerd-", "crio-", "libpod-"];
+
+fn is_container_id(text: &str) -> bool {
+    text.len() == ID_LEN && text.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Extracts the container ID from a cgroup v2 path like
+/// `/system.slice/docker-<id>.scope` (docker with systemd), `/docker/<id>` (docker with cgroupfs),
+/// `/kubepods/besteffort/pod<uid>/<id>` or `.../cri-containerd-<id>.scope` (containerd)
+/// and `/machine.slice/libpod-<id>.scope` (podman). `None` for processes outside of containers.
+pub fn container_id(cgroup: &str) -> Option<&str> {
+    // the innermost match, a container can create cgroups of its own below its scope
+    cgroup.rsplit('/').find_map(|name| {
+        let name = name.strip_suffix(".scope").unwrap_or(name);
+        let id = ID_PREFIXES
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix))
+            .unwrap_or(name);
+        // podman's libpod-conmon-<id> is the monitor next to the container, not in it
+        if is_container_id(id) { Some(id) } else { None }
+    })
+}
+
+/// Turns container IDs into names, e.g. by asking the container runtime.
+pub trait ContainerResolver: fmt::Debug {
+    /// The name of the container `id`, `None` if it isn't known.
+    fn name(&self, id: &str) -> Option<String>;
+
+    /// Called on every refresh, to pick up containers started in the meantime.
+    fn refresh(&mut self) {}
+}
+
+/// Container names from a map of IDs to names. The IDs can be given in full or cut
+/// down to at least [`SHORT_ID_LEN`] characters.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContainerNames {
+    names: HashMap<String, String>,
+    // the file the names are read from again on every refresh
+    path: Option<String>,
+}
+
+impl ContainerNames {
+    pub fn new(names: HashMap<String, String>) -> ContainerNames {
+        ContainerNames { names, path: None }
+    }
+
+    /// Parses a JSON object of IDs to names like `{"3f4e1a2b5c6d": "web"}`.
+    pub fn from_json(json: &str) -> Result<ContainerNames, String> {
+        match serde_json::from_str(json) {
+            Ok(names) => Ok(ContainerNames::new(names)),
+            Err(e) => Err(format!("Invalid container names: {}", e)),
+        }
+    }
+
+    /// Reads the names from a JSON file like [`ContainerNames::from_json`],
+    /// and again on every refresh so that the file can be kept up to date by another tool.
+    pub fn from_file(path: &str) -> Result<ContainerNames, ParserError> {
+        let json = fs::read_to_string(path).map_err(|e| ParserError::io(path, e))?;
+        let names = ContainerNames::from_json(&json).map_err(|e| ParserError::invalid(path, &e))?;
+        Ok(ContainerNames { path: Some(path.to_string()), ..names })
+    }
+}
+
+impl ContainerResolver for ContainerNames {
+    fn name(&self, id: &str) -> Option<String> {
+        if let Some(name) = self.names.get(id) {
+            return Some(name.clone());
+        }
+        self.names
+            .iter()
+            .find(|(known, _)| known.len() >= SHORT_ID_LEN && id.starts_with(known.as_str()))
+            .map(|(_, name)| name.clone())
+    }
+
+    fn refresh(&mut self) {
+        // a file that is being rewritten keeps the names read before
+        if let Some(path) = &self.path {
+            if let Ok(names) = ContainerNames::from_file(path) {
+                *self = names;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const ID: &str = "3f4e1a2b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f9012345678abcdef";
+
+    #[test]
+    fn ids_from_runtime_paths() {
+        let paths = [
+            format!("/system.slice/docker-{}.scope", ID),
+            format!("/docker/{}", ID),
+            format!("/kubepods/besteffort/pod0d5c2a1e-7b3f-4c1d-9e8a-2f6b4d3c1a0e/{}", ID),
+            format!("/kubepods.slice/kubepods-burstable.slice/cri-containerd-{}.scope", ID),
+            format!("/kubepods.slice/crio-{}.scope", ID),
+            format!("/machine.slice/libpod-{}.scope/container", ID),
+        ];
+        for path in paths.iter() {
+            assert_eq!(container_id(path), Some(ID), "{}", path);
+        }
+        assert_eq!(container_id(&format!("/machine.slice/libpod-conmon-{}.scope", ID)), None);
+        assert_eq!(container_id("/system.slice/sshd.service"), None);
+        assert_eq!(container_id(""), None);
+    }
+
+    #[test]
+    fn names_by_full_or_short_id() {
+        let names = ContainerNames::from_json(r#"{"3f4e1a2b5c6d": "web", "abc": "db"}"#).unwrap();
+        assert_eq!(names.name(ID), Some(String::from("web")));
+        // too short to be told apart from other IDs
+        assert_eq!(names.name("abcdef"), None);
+        assert!(ContainerNames::from_json("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn names_from_file() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/containers.json");
+        let mut names = ContainerNames::from_file(path).unwrap();
+        names.refresh();
+        assert_eq!(names.name(ID), Some(String::from("worker")));
+        assert!(ContainerNames::from_file("/nonexistent/containers.json").is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use std::convert::TryFrom;
+use std::process;
+
+use nix::errno::Errno;
+use nix::libc;
+use nix::sched::{self, CpuSet};
+use nix::sys::signal;
+use nix::unistd::Pid;
+
+pub use nix::sys::signal::Signal;
+
+use crate::error::ControlError;
+use crate::linux_parser::{self, FsRoot};
+
+// a pid as the kernel takes it. 0 and negative values address process groups
+// in kill(2), so they are never passed on.
+fn to_pid(pid: u32, action: &str) -> Result<Pid, ControlError> {
+    match i32::try_from(pid) {
+        Ok(raw) if raw > 0 => Ok(Pid::from_raw(raw)),
+        _ => Err(ControlError::new(pid, action, Errno::EINVAL)),
+    }
+}
+
+/// True if the pids read from `root` are the ones of our own pid namespace, which kill(2),
+/// setpriority(2) and sched_setaffinity(2) act on. The host's /proc mounted into a
+/// container lists the pids of another namespace, acting on them would hit unrelated processes.
+pub fn is_local_root(root: &FsRoot) -> bool {
+    root.proc_dir() == linux_parser::PROC_DIR
+        || linux_parser::get_self_pid(root).is_ok_and(|pid| pid == process::id())
+}
+
+/// Parses a signal name with or without the SIG prefix, in any case, or a signal number,
+/// e.g. `TERM`, `sigkill` or `9`.
+pub fn parse_signal(text: &str) -> Result<Signal, String> {
+    let text = text.trim();
+    if let Ok(number) = text.parse::<i32>() {
+        return Signal::try_from(number).map_err(|_| format!("Unknown signal {}", text));
+    }
+    let name = text.to_uppercase();
+    let name = if name.starts_with("SIG") { name } else { format!("SIG{}", name) };
+    name.parse().map_err(|_| format!("Unknown signal {}", text))
+}
+
+/// Sends `signal` to the process `pid`, like kill(1).
+pub fn send_signal(pid: u32, signal: Signal) -> Result<(), ControlError> {
+    let action = format!("send {} to", signal);
+    let target = to_pid(pid, &action)?;
+    signal::kill(target, signal).map_err(|errno| ControlError::new(pid, &action, errno))
+}
+
+// fails with ESRCH unless the task `pid` read from `root` still started at `start_time`,
+// a different start time is another process or thread that got the same id
+fn check_key(root: &FsRoot, (pid, start_time): (u32, u64), action: &str) -> Result<(), ControlError> {
+    match linux_parser::get_pid_stat(root, pid) {
+        Ok(stat) if stat.start_time == start_time => Ok(()),
+        _ => Err(ControlError::new(pid, action, Errno::ESRCH)),
+    }
+}
+
+/// Sends `signal` to the process with the key `(pid, start time)` read from `root`, see
+/// [`Process::key`](crate::Process::key). Fails with ESRCH if the pid now belongs to a
+/// process started since, so that a reused pid is never signaled.
+pub fn send_signal_to(root: &FsRoot, key: (u32, u64), signal: Signal) -> Result<(), ControlError> {
+    check_key(root, key, &format!("send {} to", signal))?;
+    send_signal(key.0, signal)
+}
+
+/// Sends `signal` to every process in `keys` like [`send_signal_to`], returning the errors of the ones that failed.
+pub fn send_signal_all(root: &FsRoot, keys: &[(u32, u64)], signal: Signal) -> Vec<ControlError> {
+    keys.iter()
+        .filter_map(|key| send_signal_to(root, *key, signal).err())
+        .collect()
+}
+
+/// Parses a nice value, from 19 (lowest priority) to -20 (highest priority).
+pub fn parse_nice(text: &str) -> Result<i32, String> {
+    match text.trim().parse::<i32>() {
+        Ok(nice) if (-20..=19).contains(&nice) => Ok(nice),
+        _ => Err(format!("Invalid nice value {}, expected -20 to 19", text)),
+    }
+}
+
+/// Sets the nice value of the task `pid`, like renice(1). On linux this only
+/// changes the thread with that id, the other threads of a process keep theirs.
+/// Lowering the nice value needs CAP_SYS_NICE.
+pub fn set_priority(pid: u32, nice: i32) -> Result<(), ControlError> {
+    let action = format!("set the nice value to {} of", nice);
+    to_pid(pid, &action)?;
+    // nix has no wrapper for setpriority(2)
+    let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice) };
+    if result == -1 {
+        return Err(ControlError::new(pid, &action, Errno::last()));
+    }
+    Ok(())
+}
+
+/// Restricts the task `pid` to the CPUs in `cpus`, like taskset(1). As with
+/// [`set_priority`], only the thread with that id is changed.
+pub fn set_affinity(pid: u32, cpus: &[usize]) -> Result<(), ControlError> {
+    let action = String::from("set the CPU affinity of");
+    let target = to_pid(pid, &action)?;
+    let mut cpu_set = CpuSet::new();
+    for cpu in cpus {
+        cpu_set.set(*cpu).map_err(|errno| ControlError::new(pid, &action, errno))?;
+    }
+    sched::sched_setaffinity(target, &cpu_set).map_err(|errno| ControlError::new(pid, &action, errno))
+}
+
+/// Like [`set_priority`] for the task with the key `(tid, start time)` read from `root`,
+/// failing with ESRCH if the tid was reused like [`send_signal_to`].
+pub fn set_priority_of(root: &FsRoot, key: (u32, u64), nice: i32) -> Result<(), ControlError> {
+    check_key(root, key, &format!("set the nice value to {} of", nice))?;
+    set_priority(key.0, nice)
+}
+
+/// Like [`set_affinity`] for the task with the key `(tid, start time)` read from `root`,
+/// failing with ESRCH if the tid was reused like [`send_signal_to`].
+pub fn set_affinity_of(root: &FsRoot, key: (u32, u64), cpus: &[usize]) -> Result<(), ControlError> {
+    check_key(root, key, "set the CPU affinity of")?;
+    set_affinity(key.0, cpus)
+}
+
+/// The `(tid, start time)` keys of every thread of the process with the key `(pid, start time)`.
+/// If the process exited or its pid was reused, only its own key is returned,
+/// so that acting on it fails instead of reaching the threads of another process.
+pub fn thread_keys(root: &FsRoot, key: (u32, u64)) -> Vec<(u32, u64)> {
+    let (pid, start_time) = key;
+    if check_key(root, key, "").is_err() {
+        return vec![key];
+    }
+    let tids = match linux_parser::get_tids(root, pid) {
+        Ok(tids) => tids,
+        Err(_) => return vec![key],
+    };
+    tids.into_iter()
+        .filter_map(|tid| {
+            // the main thread has the start time of the process
+            if tid == pid {
+                return Some((tid, start_time));
+            }
+            linux_parser::get_task_stat(root, pid, tid).ok().map(|stat| (tid, stat.start_time))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::process::Command;
+    use std::os::unix::process::ExitStatusExt;
+
+    #[test]
+    fn parse_signal_names() {
+        assert_eq!(parse_signal("TERM").unwrap(), Signal::SIGTERM);
+        assert_eq!(parse_signal("sigkill").unwrap(), Signal::SIGKILL);
+        assert_eq!(parse_signal(" 19 ").unwrap(), Signal::SIGSTOP);
+        assert_eq!(parse_signal("cont").unwrap(), Signal::SIGCONT);
+        assert!(parse_signal("FOO").is_err());
+        assert!(parse_signal("0").is_err());
+    }
+
+    #[test]
+    fn kill_child_process() {
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        send_signal(child.id(), Signal::SIGKILL).unwrap();
+        let status = child.wait().unwrap();
+        assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+    }
+
+    #[test]
+    fn renice_and_pin_child_process() {
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        // raising the nice value is always allowed for our own processes
+        let result = set_priority(child.id(), 5);
+        let affinity = sched::sched_getaffinity(Pid::this()).unwrap();
+        let cpu = (0..CpuSet::count()).find(|cpu| affinity.is_set(*cpu).unwrap_or(false)).unwrap();
+        let pinned = set_affinity(child.id(), &[cpu]);
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        result.unwrap();
+        pinned.unwrap();
+    }
+
+    #[test]
+    fn signal_only_the_same_process() {
+        let root = FsRoot::default();
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let start_time = linux_parser::get_pid_stat(&root, child.id()).unwrap().start_time;
+        // a different start time is another process that got the same pid
+        let err = send_signal_to(&root, (child.id(), start_time + 1), Signal::SIGKILL).unwrap_err();
+        assert_eq!(err.errno, Errno::ESRCH);
+        let errors = send_signal_all(&root, &[(child.id(), start_time)], Signal::SIGKILL);
+        let status = child.wait().unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(status.signal(), Some(Signal::SIGKILL as i32));
+    }
+
+    #[test]
+    fn renice_only_the_same_process() {
+        let root = FsRoot::default();
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let start_time = linux_parser::get_pid_stat(&root, child.id()).unwrap().start_time;
+        let key = (child.id(), start_time);
+        let reused = (child.id(), start_time + 1);
+        let reniced = set_priority_of(&root, reused, 5);
+        let pinned = set_affinity_of(&root, reused, &[0]);
+        let keys = thread_keys(&root, key);
+        let stale_keys = thread_keys(&root, reused);
+        let result = set_priority_of(&root, key, 5);
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert_eq!(reniced.unwrap_err().errno, Errno::ESRCH);
+        assert_eq!(pinned.unwrap_err().errno, Errno::ESRCH);
+        assert_eq!(keys, vec![key]);
+        assert_eq!(stale_keys, vec![reused]);
+        result.unwrap();
+    }
+
+    #[test]
+    fn local_roots() {
+        assert!(is_local_root(&FsRoot::default()));
+        assert!(is_local_root(&FsRoot::new("/proc", "/etc")));
+        // no self link, like a tree of another pid namespace
+        assert!(!is_local_root(&linux_parser::tests::fixture_root()));
+    }
+
+    #[test]
+    fn parse_nice_values() {
+        assert_eq!(parse_nice("-5").unwrap(), -5);
+        assert_eq!(parse_nice(" 19").unwrap(), 19);
+        assert!(parse_nice("20").is_err());
+        assert!(parse_nice("low").is_err());
+    }
+
+    #[test]
+    fn process_groups_are_rejected() {
+        let err = send_signal(0, Signal::SIGTERM).unwrap_err();
+        assert_eq!(err.errno, Errno::EINVAL);
+        let err = send_signal(u32::MAX, Signal::SIGTERM).unwrap_err();
+        assert_eq!(err.errno, Errno::EINVAL);
+    }
+}
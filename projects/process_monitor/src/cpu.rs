@@ -1,58 +1,197 @@
 use std::fmt;
 
-use crate::linux_parser;
+use crate::error::ParserError;
+use crate::linux_parser::{self, CpuTimes, FsRoot};
 use crate::format;
 
+/// Share of the time between two refreshes spent in each state, as fractions of 1.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CpuBreakdown {
+    pub user: f64,
+    pub nice: f64,
+    pub system: f64,
+    pub idle: f64,
+    pub iowait: f64,
+    pub irq: f64,
+    pub softirq: f64,
+    pub steal: f64,
+    pub guest: f64,
+}
+
+impl CpuBreakdown {
+    fn between(prev: &CpuTimes, current: &CpuTimes) -> CpuBreakdown {
+        let change_total = current.total().saturating_sub(prev.total());
+        // don't divide by zero
+        if change_total == 0 {
+            return CpuBreakdown::default();
+        }
+        let share = |current: u64, prev: u64| {
+            current.saturating_sub(prev) as f64 / change_total as f64
+        };
+        // user and nice include the guest time, report it separately
+        CpuBreakdown {
+            user: share(current.user.saturating_sub(current.guest), prev.user.saturating_sub(prev.guest)),
+            nice: share(current.nice.saturating_sub(current.guest_nice), prev.nice.saturating_sub(prev.guest_nice)),
+            system: share(current.system, prev.system),
+            idle: share(current.idle, prev.idle),
+            iowait: share(current.iowait, prev.iowait),
+            irq: share(current.irq, prev.irq),
+            softirq: share(current.softirq, prev.softirq),
+            steal: share(current.steal, prev.steal),
+            guest: share(current.guest + current.guest_nice, prev.guest + prev.guest_nice),
+        }
+    }
+}
+
+impl fmt::Display for CpuBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.1} us, {:.1} ni, {:.1} sy, {:.1} id, {:.1} wa, {:.1} hi, {:.1} si, {:.1} st, {:.1} gu",
+            self.user * 100.0,
+            self.nice * 100.0,
+            self.system * 100.0,
+            self.idle * 100.0,
+            self.iowait * 100.0,
+            self.irq * 100.0,
+            self.softirq * 100.0,
+            self.steal * 100.0,
+            self.guest * 100.0,
+        )
+    }
+}
+
+/// CPU usage between the last two refreshes, in total and per core.
 #[derive(Debug)]
 pub struct Cpu {
     n_cores: u32,
-    prev_jif: (u64, u64),
-    current_jif: (u64, u64),
+    prev_times: CpuTimes,
+    current_times: CpuTimes,
+    prev_core_times: Vec<CpuTimes>,
+    current_core_times: Vec<CpuTimes>,
 }
 
 impl fmt::Display for Cpu {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f, 
-            "- Cores Cnt: {}\n- Utilization: {}", 
+            "- Cores Cnt: {}\n- Utilization: {}\n- Breakdown[%]: {}", 
             self.get_cpu_count(),
-            format::bar(50, self.utilization())
-        )
+            format::bar(50, self.utilization()),
+            self.breakdown()
+        )?;
+        for (core, utilization) in self.core_utilization().iter().enumerate() {
+            write!(f, "\n  - Core {:<3} {}", core, format::bar(50, *utilization))?;
+        }
+        Ok(())
     }
 }
 
+// fraction of the jiffies between two samples spent active
+fn jiffies_utilization(prev: &CpuTimes, current: &CpuTimes) -> f64 {
+    let change_active = current.active_jiffies().saturating_sub(prev.active_jiffies());
+    let change_total = current.total().saturating_sub(prev.total());
+    // don't divide by zero
+    if change_total == 0 {
+        return 0.0;
+    }
+    (change_active as f64 / change_total as f64).min(1.0)
+}
+
 impl Cpu {
-    pub fn new() -> Cpu {
-        let n_cores = linux_parser::get_cpu_cnt();
-        let current_jif = linux_parser::get_cpu_utilization();
+    pub fn new(root: &FsRoot) -> Result<Cpu, ParserError> {
+        let current_times = linux_parser::get_cpu_times(root)?;
+        let current_core_times = linux_parser::get_core_times(root)?;
+        let n_cores = current_core_times.len() as u32;
 
-        Cpu {
+        Ok(Cpu {
             n_cores,
-            prev_jif: (0, 0),
-            current_jif
-        }
+            prev_times: CpuTimes::default(),
+            current_times,
+            prev_core_times: vec![CpuTimes::default(); current_core_times.len()],
+            current_core_times,
+        })
     }
 
+    /// Fraction of the time all cores were busy, from 0 to 1.
     pub fn utilization(&self) -> f64 {
-        let change_active = self.current_jif.0 - self.prev_jif.0;
-        let change_total = self.current_jif.1 - self.prev_jif.1;
-        // don't divide by zero
-        if change_total == 0 {
-            return 0.0;
-        }
-        change_active as f64 / change_total as f64
+        jiffies_utilization(&self.prev_times, &self.current_times)
+    }
+
+    pub fn breakdown(&self) -> CpuBreakdown {
+        CpuBreakdown::between(&self.prev_times, &self.current_times)
     }
 
-    pub fn refresh(&mut self) {
-        self.prev_jif = self.current_jif;
-        self.current_jif = linux_parser::get_cpu_utilization();
+    /// Fraction of the time each core was busy, in core order.
+    pub fn core_utilization(&self) -> Vec<f64> {
+        self.current_core_times
+            .iter()
+            .zip(self.prev_core_times.iter())
+            .map(|(current, prev)| jiffies_utilization(prev, current))
+            .collect()
+    }
+
+    pub fn refresh(&mut self, root: &FsRoot) -> Result<(), ParserError> {
+        let current_times = linux_parser::get_cpu_times(root)?;
+        let current_core_times = linux_parser::get_core_times(root)?;
+
+        self.prev_times = self.current_times;
+        self.current_times = current_times;
+
+        // cores can go on- and offline between refreshes
+        self.prev_core_times = if current_core_times.len() == self.current_core_times.len() {
+            std::mem::take(&mut self.current_core_times)
+        } else {
+            vec![CpuTimes::default(); current_core_times.len()]
+        };
+        self.n_cores = current_core_times.len() as u32;
+        self.current_core_times = current_core_times;
+        Ok(())
     }
 
     pub fn get_cpu_count(&self) -> u32 {
         self.n_cores
     }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn times(user: u64, idle: u64, iowait: u64, steal: u64) -> CpuTimes {
+        CpuTimes { user, idle, iowait, steal, ..CpuTimes::default() }
+    }
+
+    #[test]
+    fn test_jiffies_utilization() {
+        let prev = times(100, 100, 0, 0);
+        let current = times(160, 120, 5, 15);
+        assert_eq!(jiffies_utilization(&prev, &current), 0.75);
+    }
+
+    #[test]
+    fn test_jiffies_utilization_no_change() {
+        let prev = times(100, 100, 0, 0);
+        assert_eq!(jiffies_utilization(&prev, &prev), 0.0);
+    }
+
+    #[test]
+    fn test_breakdown() {
+        let prev = times(100, 100, 0, 0);
+        let current = times(150, 120, 10, 20);
+        let breakdown = CpuBreakdown::between(&prev, &current);
+        assert_eq!(breakdown.user, 0.5);
+        assert_eq!(breakdown.idle, 0.2);
+        assert_eq!(breakdown.iowait, 0.1);
+        assert_eq!(breakdown.steal, 0.2);
+    }
 
-    pub fn total_jif(&self) -> u64 {
-        self.current_jif.0 + self.current_jif.1
+    #[test]
+    fn test_core_utilization() {
+        let root = linux_parser::tests::fixture_root();
+        let cpu = Cpu::new(&root).unwrap();
+        assert_eq!(cpu.get_cpu_count(), 2);
+        assert_eq!(cpu.core_utilization().len(), 2);
     }
 }
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::ParserError;
+use crate::format;
+use crate::linux_parser::{self, DiskStats, FsRoot};
+
+const SECTOR_SIZE: u64 = 512;
+
+// name prefixes of devices that aren't backed by their own hardware
+const VIRTUAL_PREFIXES: [&str; 6] = ["loop", "ram", "zram", "dm-", "md", "nbd"];
+
+/// What a line of /proc/diskstats stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskKind {
+    /// A whole physical device like sda or nvme0n1.
+    Disk,
+    /// A partition of another device, its I/O is counted in the device as well.
+    Partition,
+    /// Loop, RAM, device mapper and software RAID devices. Their I/O ends up on
+    /// a physical device as well.
+    Virtual,
+}
+
+// sda1 is a partition of sda, nvme0n1p1 of nvme0n1 and mmcblk0p1 of mmcblk0
+fn is_partition_of(name: &str, device: &str) -> bool {
+    let rest = match name.strip_prefix(device) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    // a p separates the partition number from a device name ending with a digit
+    let rest = if device.ends_with(|c: char| c.is_ascii_digit()) {
+        match rest.strip_prefix('p') {
+            Some(rest) => rest,
+            None => return false,
+        }
+    } else {
+        rest
+    };
+    !rest.is_empty() && linux_parser::is_numeric(rest)
+}
+
+fn classify(name: &str, names: &[&str]) -> DiskKind {
+    if VIRTUAL_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+        return DiskKind::Virtual;
+    }
+    if names.iter().any(|device| *device != name && is_partition_of(name, device)) {
+        return DiskKind::Partition;
+    }
+    DiskKind::Disk
+}
+
+/// The I/O of one block device between the last two refreshes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskUsage {
+    pub name: String,
+    pub kind: DiskKind,
+    /// Bytes per second.
+    pub read_rate: f64,
+    pub write_rate: f64,
+    /// Completed requests per second.
+    pub read_iops: f64,
+    pub write_iops: f64,
+    /// Average time a request took, queueing included, in milliseconds.
+    pub latency_ms: f64,
+    /// Fraction of the time the device was busy, from 0 to 1.
+    pub utilization: f64,
+}
+
+impl DiskUsage {
+    fn between(prev: &DiskStats, current: &DiskStats, kind: DiskKind, seconds: f64) -> DiskUsage {
+        let delta = |current: u64, prev: u64| current.saturating_sub(prev);
+        let per_second = |count: u64| {
+            // don't divide by zero
+            if seconds <= 0.0 {
+                return 0.0;
+            }
+            count as f64 / seconds
+        };
+        let requests = delta(current.reads, prev.reads) + delta(current.writes, prev.writes);
+        let request_ms = delta(current.read_ms, prev.read_ms) + delta(current.write_ms, prev.write_ms);
+        DiskUsage {
+            name: current.name.clone(),
+            kind,
+            read_rate: per_second(delta(current.sectors_read, prev.sectors_read) * SECTOR_SIZE),
+            write_rate: per_second(delta(current.sectors_written, prev.sectors_written) * SECTOR_SIZE),
+            read_iops: per_second(delta(current.reads, prev.reads)),
+            write_iops: per_second(delta(current.writes, prev.writes)),
+            latency_ms: if requests == 0 { 0.0 } else { request_ms as f64 / requests as f64 },
+            utilization: (per_second(delta(current.io_ms, prev.io_ms)) / 1000.0).min(1.0),
+        }
+    }
+}
+
+// reads /proc/diskstats, None if it can't be read, e.g. in some containers and sandboxes
+fn read_disk_stats(root: &FsRoot) -> Result<Option<Vec<DiskStats>>, ParserError> {
+    match linux_parser::get_disk_stats(root) {
+        Ok(stats) => Ok(Some(stats)),
+        Err(ParserError::Io { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Block device I/O from /proc/diskstats, between the last two refreshes.
+/// Without a readable /proc/diskstats there are no disks and the panel says so.
+#[derive(Debug)]
+pub struct Disks {
+    prev_stats: HashMap<String, DiskStats>,
+    current_stats: Vec<DiskStats>,
+    prev_sampled_at: f64,
+    sampled_at: f64,
+    available: bool,
+}
+
+impl fmt::Display for Disks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.available {
+            return write!(f, "- Not available");
+        }
+        let disks = self.physical_usage();
+        if disks.is_empty() {
+            return write!(f, "- No disks");
+        }
+        for (i, disk) in disks.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "- {:<10} Read: {:>8}/s  Write: {:>8}/s  IOPS: {:.0} r, {:.0} w  Latency: {:.1} ms  Util: {:.1}%",
+                disk.name,
+                format::format_bytes(disk.read_rate),
+                format::format_bytes(disk.write_rate),
+                disk.read_iops,
+                disk.write_iops,
+                disk.latency_ms,
+                disk.utilization * 100.0,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Disks {
+    pub fn new(root: &FsRoot) -> Result<Disks, ParserError> {
+        let current_stats = read_disk_stats(root)?;
+        let sampled_at = linux_parser::get_uptime(root)?;
+        // until the first refresh the rates are averages since boot
+        Ok(Disks {
+            prev_stats: HashMap::new(),
+            available: current_stats.is_some(),
+            current_stats: current_stats.unwrap_or_default(),
+            prev_sampled_at: 0.0,
+            sampled_at,
+        })
+    }
+
+    /// False if /proc/diskstats can't be read.
+    pub fn is_available(&self) -> bool { self.available }
+
+    /// Every device and partition, in the order of /proc/diskstats.
+    pub fn usage(&self) -> Vec<DiskUsage> {
+        let names: Vec<&str> = self.current_stats.iter().map(|disk| disk.name.as_str()).collect();
+        let seconds = self.sampled_at - self.prev_sampled_at;
+        self.current_stats
+            .iter()
+            .map(|current| {
+                // a device that showed up since the last refresh starts from zero
+                let prev = self.prev_stats.get(&current.name).cloned().unwrap_or_default();
+                DiskUsage::between(&prev, current, classify(&current.name, &names), seconds)
+            })
+            .collect()
+    }
+
+    /// The whole physical devices, leaving out partitions and virtual devices
+    /// so that no I/O is counted twice.
+    pub fn physical_usage(&self) -> Vec<DiskUsage> {
+        self.usage().into_iter().filter(|disk| disk.kind == DiskKind::Disk).collect()
+    }
+
+    pub fn refresh(&mut self, root: &FsRoot) -> Result<(), ParserError> {
+        let current_stats = read_disk_stats(root)?;
+        let sampled_at = linux_parser::get_uptime(root)?;
+
+        self.available = current_stats.is_some();
+        let current_stats = current_stats.unwrap_or_default();
+        let prev_stats = std::mem::replace(&mut self.current_stats, current_stats);
+        self.prev_stats = prev_stats.into_iter().map(|disk| (disk.name.clone(), disk)).collect();
+        self.prev_sampled_at = self.sampled_at;
+        self.sampled_at = sampled_at;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn classify_devices() {
+        let names = ["sda", "sda1", "nvme0n1", "nvme0n1p2", "mmcblk0", "mmcblk0p1", "loop0", "dm-0", "sr0"];
+        assert_eq!(classify("sda", &names), DiskKind::Disk);
+        assert_eq!(classify("sda1", &names), DiskKind::Partition);
+        assert_eq!(classify("nvme0n1", &names), DiskKind::Disk);
+        assert_eq!(classify("nvme0n1p2", &names), DiskKind::Partition);
+        assert_eq!(classify("mmcblk0p1", &names), DiskKind::Partition);
+        assert_eq!(classify("loop0", &names), DiskKind::Virtual);
+        assert_eq!(classify("dm-0", &names), DiskKind::Virtual);
+        assert_eq!(classify("sr0", &names), DiskKind::Disk);
+    }
+
+    #[test]
+    fn usage_between_samples() {
+        let prev = DiskStats {
+            name: String::from("sda"),
+            reads: 100,
+            sectors_read: 1000,
+            read_ms: 50,
+            writes: 100,
+            write_ms: 50,
+            io_ms: 1000,
+            ..DiskStats::default()
+        };
+        let current = DiskStats {
+            reads: 300,
+            sectors_read: 5000,
+            read_ms: 250,
+            writes: 300,
+            sectors_written: 2000,
+            write_ms: 450,
+            io_ms: 1500,
+            ..prev.clone()
+        };
+        let usage = DiskUsage::between(&prev, &current, DiskKind::Disk, 2.0);
+        assert_eq!(usage.read_rate, 1024000.0);
+        assert_eq!(usage.write_rate, 512000.0);
+        assert_eq!(usage.read_iops, 100.0);
+        assert_eq!(usage.latency_ms, 1.5);
+        assert_eq!(usage.utilization, 0.25);
+    }
+
+    #[test]
+    fn fixture_physical_disks() {
+        let mut disks = Disks::new(&linux_parser::tests::fixture_root()).unwrap();
+        disks.refresh(&linux_parser::tests::fixture_root()).unwrap();
+        let names: Vec<String> = disks.physical_usage().into_iter().map(|disk| disk.name).collect();
+        assert_eq!(names, vec!["sda", "nvme0n1"]);
+        // no time passed between the two samples
+        assert!(disks.usage().iter().all(|disk| disk.read_rate == 0.0));
+    }
+
+    #[test]
+    fn without_diskstats() {
+        let root = linux_parser::tests::minimal_root();
+        let mut disks = Disks::new(&root).unwrap();
+        disks.refresh(&root).unwrap();
+        assert!(!disks.is_available());
+        assert!(disks.usage().is_empty());
+        assert_eq!(disks.to_string(), "- Not available");
+    }
+}
@@ -0,0 +1,109 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use nix::errno::Errno;
+
+/// Error returned when a file under /proc or /etc cannot be read or parsed.
+#[derive(Debug)]
+pub enum ParserError {
+    /// A file could not be opened or read.
+    Io { path: String, source: io::Error },
+    /// A file was read but the expected key or column isn't there.
+    MissingField { path: String, field: String },
+    /// A value was found but could not be converted.
+    InvalidValue { path: String, value: String },
+}
+
+impl ParserError {
+    pub fn io(path: &str, source: io::Error) -> ParserError {
+        ParserError::Io { path: path.to_string(), source }
+    }
+
+    pub fn missing(path: &str, field: &str) -> ParserError {
+        ParserError::MissingField { path: path.to_string(), field: field.to_string() }
+    }
+
+    pub fn invalid(path: &str, value: &str) -> ParserError {
+        ParserError::InvalidValue { path: path.to_string(), value: value.to_string() }
+    }
+
+    /// True if the error comes from a process that exited while it was being read.
+    pub fn is_vanished(&self) -> bool {
+        match self {
+            // reading an already opened file of an exited process gives ESRCH
+            ParserError::Io { source, .. } => {
+                source.kind() == io::ErrorKind::NotFound
+                    || source.raw_os_error() == Some(nix::libc::ESRCH)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParserError::Io { path, source } => write!(f, "Failed to read {}: {}", path, source),
+            ParserError::MissingField { path, field } => write!(f, "Missing {} in {}", field, path),
+            ParserError::InvalidValue { path, value } => write!(f, "Invalid value {:?} in {}", value, path),
+        }
+    }
+}
+
+impl Error for ParserError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParserError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Error returned when an action on a process, like sending a signal, fails.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlError {
+    pub pid: u32,
+    /// What was attempted, e.g. "send SIGTERM to".
+    pub action: String,
+    pub errno: Errno,
+}
+
+impl ControlError {
+    pub fn new(pid: u32, action: &str, errno: Errno) -> ControlError {
+        ControlError { pid, action: action.to_string(), errno }
+    }
+
+    /// True if the process belongs to another user and we lack the privileges.
+    pub fn is_permission_denied(&self) -> bool {
+        self.errno == Errno::EPERM || self.errno == Errno::EACCES
+    }
+}
+
+impl fmt::Display for ControlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to {} {}: {}", self.action, self.pid, self.errno.desc())
+    }
+}
+
+impl Error for ControlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.errno)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn vanished_process() {
+        let err = ParserError::io("/proc/1/stat", io::Error::from(io::ErrorKind::NotFound));
+        assert!(err.is_vanished());
+        let err = ParserError::io("/proc/1/stat", io::Error::from_raw_os_error(nix::libc::ESRCH));
+        assert!(err.is_vanished());
+        let err = ParserError::missing("/proc/1/status", "Uid");
+        assert!(!err.is_vanished());
+    }
+}
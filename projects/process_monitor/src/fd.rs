@@ -0,0 +1,135 @@
+use std::fmt;
+
+use crate::error::ParserError;
+use crate::linux_parser::{self, FsRoot};
+
+/// A process is near its limit once this share of its open files limit is in use.
+pub const NEAR_LIMIT_RATIO: f64 = 0.8;
+
+const DELETED_SUFFIX: &str = " (deleted)";
+
+/// What a file descriptor points to, from the target of its /proc/[pid]/fd link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FdKind {
+    File,
+    Socket,
+    Pipe,
+    /// eventfd, epoll, inotify, timerfd and other files without an inode of their own.
+    AnonInode,
+    /// A file that was removed while still open, its disk space isn't freed until it's closed.
+    Deleted,
+}
+
+impl FdKind {
+    pub fn classify(target: &str) -> FdKind {
+        if target.starts_with("socket:[") {
+            FdKind::Socket
+        } else if target.starts_with("pipe:[") {
+            FdKind::Pipe
+        } else if target.starts_with("anon_inode:") {
+            FdKind::AnonInode
+        } else if target.ends_with(DELETED_SUFFIX) {
+            FdKind::Deleted
+        } else {
+            FdKind::File
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            FdKind::File => "file",
+            FdKind::Socket => "socket",
+            FdKind::Pipe => "pipe",
+            FdKind::AnonInode => "anon_inode",
+            FdKind::Deleted => "deleted",
+        }
+    }
+}
+
+/// The inode of a `socket:[inode]` link target, `None` for anything else.
+pub fn socket_inode(target: &str) -> Option<u64> {
+    target.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+}
+
+/// One open file descriptor of a process.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenFd {
+    pub fd: u32,
+    pub target: String,
+    pub kind: FdKind,
+}
+
+impl OpenFd {
+    pub fn head_str() -> String {
+        format!("{:>6} {:<10} {:<60}", "FD", "TYPE", "TARGET")
+    }
+}
+
+impl fmt::Display for OpenFd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>6} {:<10} {:<60}", self.fd, self.kind.label(), self.target)
+    }
+}
+
+/// Reads every open file descriptor of `pid`, sorted by number.
+/// Only readable for your own processes unless running as root.
+pub fn read_fds(root: &FsRoot, pid: u32) -> Result<Vec<OpenFd>, ParserError> {
+    let mut fds = linux_parser::get_fds(root, pid)?;
+    fds.sort_unstable();
+    let mut open_fds: Vec<OpenFd> = Vec::new();
+    for fd in fds {
+        let target = match linux_parser::get_fd_target(root, pid, fd) {
+            Ok(target) => target,
+            // closed since the directory was listed
+            Err(e) if e.is_vanished() => continue,
+            Err(e) => return Err(e),
+        };
+        let kind = FdKind::classify(&target);
+        open_fds.push(OpenFd { fd, target, kind });
+    }
+    Ok(open_fds)
+}
+
+/// True if `count` open files use up most of the soft limit `limit`.
+pub fn is_near_limit(count: usize, limit: Option<u64>) -> bool {
+    match limit {
+        Some(limit) => count as f64 >= limit as f64 * NEAR_LIMIT_RATIO,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn classify_targets() {
+        assert_eq!(FdKind::classify("/dev/pts/0"), FdKind::File);
+        assert_eq!(FdKind::classify("socket:[2001]"), FdKind::Socket);
+        assert_eq!(FdKind::classify("pipe:[1001]"), FdKind::Pipe);
+        assert_eq!(FdKind::classify("anon_inode:[eventpoll]"), FdKind::AnonInode);
+        assert_eq!(FdKind::classify("anon_inode:inotify"), FdKind::AnonInode);
+        assert_eq!(FdKind::classify("/tmp/cache.db (deleted)"), FdKind::Deleted);
+        assert_eq!(socket_inode("socket:[2001]"), Some(2001));
+        assert_eq!(socket_inode("pipe:[1001]"), None);
+    }
+
+    #[test]
+    fn fixture_fds() {
+        let fds = read_fds(&linux_parser::tests::fixture_root(), 42).unwrap();
+        let kinds: Vec<FdKind> = fds.iter().map(|fd| fd.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![FdKind::File, FdKind::Pipe, FdKind::Deleted, FdKind::Socket, FdKind::AnonInode, FdKind::Socket]
+        );
+        assert!(fds[2].to_string().contains("deleted    /var/log/worker.log (deleted)"));
+    }
+
+    #[test]
+    fn near_limit() {
+        assert!(is_near_limit(5, Some(6)));
+        assert!(!is_near_limit(4, Some(6)));
+        assert!(!is_near_limit(100000, None));
+    }
+}
@@ -36,7 +36,11 @@ pub fn read_as_hashmap(path: &str, delimiter: &str) -> Result<HashMap<String, St
     let lines = iter_lines(path)?;
     for line in lines {
         let line = line?;
-        let split_loc = line.find(delimiter).unwrap();
+        // skip blank lines and lines without a key
+        let split_loc = match line.find(delimiter) {
+            Some(loc) => loc,
+            None => continue,
+        };
         let (key, value) = line.split_at(split_loc);
 
         let key = key.to_string();
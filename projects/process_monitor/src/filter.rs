@@ -0,0 +1,162 @@
+use std::fmt;
+
+use regex::Regex;
+
+use crate::process::Process;
+
+/// How the command line of a process is matched.
+#[derive(Debug, Clone)]
+pub enum CommandPattern {
+    Substring(String),
+    Regex(Regex),
+}
+
+impl CommandPattern {
+    pub fn regex(pattern: &str) -> Result<CommandPattern, String> {
+        match Regex::new(pattern) {
+            Ok(regex) => Ok(CommandPattern::Regex(regex)),
+            Err(e) => Err(format!("Invalid command regex {}: {}", pattern, e)),
+        }
+    }
+
+    pub fn is_match(&self, command: &str) -> bool {
+        // match on the command line as it is displayed
+        let command = command.replace('\0', " ");
+        match self {
+            CommandPattern::Substring(text) => command.contains(text.as_str()),
+            CommandPattern::Regex(regex) => regex.is_match(&command),
+        }
+    }
+}
+
+/// Narrows down the process list. Empty criteria match every process,
+/// a process has to match all of the others.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessFilter {
+    /// User names or uids.
+    pub users: Vec<String>,
+    pub pids: Vec<u32>,
+    pub command: Option<CommandPattern>,
+    /// Container names or IDs, IDs may be cut down like `docker ps` shows them.
+    pub containers: Vec<String>,
+}
+
+impl ProcessFilter {
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty() && self.pids.is_empty() && self.command.is_none() && self.containers.is_empty()
+    }
+
+    pub fn matches(&self, process: &Process) -> bool {
+        let user_matches = self.users.is_empty()
+            || self.users.iter().any(|user| *user == process.user() || *user == process.uid());
+        let pid_matches = self.pids.is_empty() || self.pids.contains(&process.pid());
+        let command_matches = match &self.command {
+            Some(pattern) => pattern.is_match(process.command()),
+            None => true,
+        };
+        let container_matches = self.containers.is_empty()
+            || self.containers.iter().any(|container| {
+                process.container_name() == Some(container.as_str())
+                    || process.container_id().is_some_and(|id| id.starts_with(container.as_str()))
+            });
+        user_matches && pid_matches && command_matches && container_matches
+    }
+}
+
+/// Splits a comma separated list like "root,1000".
+pub fn parse_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Parses a comma separated list of pids like "1,42".
+pub fn parse_pids(list: &str) -> Result<Vec<u32>, String> {
+    parse_list(list)
+        .iter()
+        .map(|pid| pid.parse().map_err(|_| format!("Invalid pid {}", pid)))
+        .collect()
+}
+
+impl fmt::Display for ProcessFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut criteria: Vec<String> = vec![];
+        if !self.users.is_empty() {
+            criteria.push(format!("user={}", self.users.join(",")));
+        }
+        if !self.pids.is_empty() {
+            let pids: Vec<String> = self.pids.iter().map(|pid| pid.to_string()).collect();
+            criteria.push(format!("pid={}", pids.join(",")));
+        }
+        match &self.command {
+            Some(CommandPattern::Substring(text)) => criteria.push(format!("command~{}", text)),
+            Some(CommandPattern::Regex(regex)) => criteria.push(format!("command=/{}/", regex)),
+            None => {}
+        }
+        if !self.containers.is_empty() {
+            criteria.push(format!("container={}", self.containers.join(",")));
+        }
+        write!(f, "{}", criteria.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::linux_parser;
+
+    fn matching_pids(filter: &ProcessFilter) -> Vec<u32> {
+        linux_parser::tests::fixture_processes()
+            .iter()
+            .filter(|p| filter.matches(p))
+            .map(|p| p.pid())
+            .collect()
+    }
+
+    #[test]
+    fn empty_filter_matches_all() {
+        assert_eq!(matching_pids(&ProcessFilter::default()), vec![1, 2, 42]);
+    }
+
+    #[test]
+    fn filter_by_user_name_or_uid() {
+        let filter = ProcessFilter { users: parse_list("alice"), ..ProcessFilter::default() };
+        assert_eq!(matching_pids(&filter), vec![42]);
+        let filter = ProcessFilter { users: parse_list("0"), ..ProcessFilter::default() };
+        assert_eq!(matching_pids(&filter), vec![1, 2]);
+    }
+
+    #[test]
+    fn filter_by_pids_and_command() {
+        let filter = ProcessFilter {
+            pids: parse_pids("1, 42").unwrap(),
+            command: Some(CommandPattern::Substring(String::from("init splash"))),
+            ..ProcessFilter::default()
+        };
+        assert_eq!(matching_pids(&filter), vec![1]);
+        assert!(parse_pids("1,x").is_err());
+    }
+
+    #[test]
+    fn filter_by_command_regex() {
+        let filter = ProcessFilter {
+            command: Some(CommandPattern::regex(r"^python\d? ").unwrap()),
+            ..ProcessFilter::default()
+        };
+        assert_eq!(matching_pids(&filter), vec![42]);
+        assert!(CommandPattern::regex("(").is_err());
+    }
+
+    #[test]
+    fn filter_by_container() {
+        let filter = ProcessFilter { containers: parse_list("3f4e1a2b5c6d"), ..ProcessFilter::default() };
+        assert_eq!(matching_pids(&filter), vec![42]);
+        assert_eq!(filter.to_string(), "container=3f4e1a2b5c6d");
+        // names are only known once a resolver named the containers
+        let filter = ProcessFilter { containers: parse_list("worker"), ..ProcessFilter::default() };
+        assert!(matching_pids(&filter).is_empty());
+    }
+}
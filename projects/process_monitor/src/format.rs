@@ -10,6 +10,21 @@ pub fn format_seconds(seconds: u64) -> String {
     format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
 }
 
+/// Bytes with a binary unit suffix, e.g. `512B`, `1.5K` or `12.0M`.
+pub fn format_bytes(bytes: f64) -> String {
+    let units = ["K", "M", "G", "T"];
+    if bytes < 1024.0 {
+        return format!("{:.0}B", bytes);
+    }
+    let mut value = bytes / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", value, units[unit])
+}
+
 pub fn bar(size: u32, utilization: f64) -> String {
     //
     let bar_size = (utilization * size as f64) as usize;
@@ -48,6 +63,13 @@ mod tests {
         assert_eq!(result, String::from("200:00:00"));
     }
 
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512.0), String::from("512B"));
+        assert_eq!(format_bytes(1536.0), String::from("1.5K"));
+        assert_eq!(format_bytes(12.0 * 1024.0 * 1024.0), String::from("12.0M"));
+    }
+
     #[test]
     fn test_format_seconds_3h3m3s() {
         let result = format_seconds(10983);
@@ -0,0 +1,59 @@
+//! Snapshots of a linux system read from /proc, like `top`.
+//!
+//! [`System`] takes a snapshot of the CPU, the memory, the disks, the network and every process,
+//! and updates it in place on each [`System::refresh`].
+//!
+//! ```no_run
+//! use process_monitor::{FsRoot, System};
+//!
+//! let mut sys = System::new(FsRoot::default()).unwrap();
+//! sys.refresh().unwrap();
+//! println!("CPU: {:.1}%", sys.cpu().utilization() * 100.0);
+//! for process in sys.processes() {
+//!     println!("{} {:.1}%", process.pid(), process.cpu_utilization());
+//! }
+//! ```
+//!
+//! [`Snapshot`] turns a refreshed [`System`] into a versioned JSON document.
+//!
+//! The /proc and /etc trees can be read from another place with [`FsRoot::new`],
+//! e.g. when the host's procfs is mounted into a container.
+
+pub mod linux_parser;
+pub mod process;
+pub mod system;
+pub mod format;
+pub mod cpu;
+pub mod memory;
+pub mod disk;
+pub mod network;
+pub mod pressure;
+pub mod error;
+pub mod filter;
+pub mod tree;
+pub mod thread;
+pub mod cgroup;
+pub mod container;
+pub mod fd;
+pub mod socket;
+pub mod control;
+pub mod snapshot;
+mod file_utils;
+
+pub use crate::cgroup::CgroupUsage;
+pub use crate::container::{ContainerNames, ContainerResolver};
+pub use crate::cpu::{Cpu, CpuBreakdown};
+pub use crate::disk::{DiskKind, DiskUsage, Disks};
+pub use crate::fd::{FdKind, OpenFd};
+pub use crate::error::{ControlError, ParserError};
+pub use crate::filter::{CommandPattern, ProcessFilter};
+pub use crate::linux_parser::FsRoot;
+pub use crate::memory::{MemInfo, MemScale};
+pub use crate::network::{InterfaceUsage, Network};
+pub use crate::pressure::Pressure;
+pub use crate::process::{Process, SortKey, SortOrder};
+pub use crate::snapshot::{Snapshot, SCHEMA_VERSION};
+pub use crate::socket::{Protocol, Socket, SocketRow};
+pub use crate::system::System;
+pub use crate::thread::{Thread, ThreadScope};
+pub use crate::tree::TreeRow;
@@ -1,36 +1,127 @@
 
 use std::fs::{self, File};
 use std::io::{self, BufRead};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::path::Path;
+use std::str::FromStr;
 use std::vec;
 
 use nix::unistd;
 
+use crate::error::ParserError;
 use crate::file_utils;
 
 // files pathes
 pub const PROC_DIR: &str = "/proc/";
+pub const ETC_DIR: &str = "/etc/";
+pub const SYS_DIR: &str = "/sys/";
 pub const VERSION_FILENAME: &str = "version";
 pub const MEM_FILENAME: &str = "meminfo";
 pub const UPTIME_FILENAME: &str = "uptime";
 pub const STAT_FILENAME: &str = "stat";
+pub const LOADAVG_FILENAME: &str = "loadavg";
+pub const PRESSURE_DIRNAME: &str = "pressure";
+pub const DISKSTATS_FILENAME: &str = "diskstats";
+pub const NET_DEV_FILENAME: &str = "net/dev";
+pub const NET_TCP_FILENAME: &str = "net/tcp";
+pub const NET_TCP6_FILENAME: &str = "net/tcp6";
+pub const NET_UDP_FILENAME: &str = "net/udp";
+pub const NET_UDP6_FILENAME: &str = "net/udp6";
+pub const NET_UNIX_FILENAME: &str = "net/unix";
+pub const LOCAL_PORT_RANGE_FILENAME: &str = "sys/net/ipv4/ip_local_port_range";
 pub const STATUS_FILENAME: &str = "status";
 pub const CMDLINE_FILENAME: &str = "cmdline";
-pub const PASSWD_PATH: &str = "/etc/passwd";
-pub const OS_PATH: &str = "/etc/os-release";
+pub const COMM_FILENAME: &str = "comm";
+pub const IO_FILENAME: &str = "io";
+pub const CGROUP_FILENAME: &str = "cgroup";
+pub const CGROUP_DIRNAME: &str = "fs/cgroup";
+pub const CGROUP_HYBRID_DIRNAME: &str = "fs/cgroup/unified";
+pub const CGROUP_CONTROLLERS_FILENAME: &str = "cgroup.controllers";
+pub const MEMORY_MAX_FILENAME: &str = "memory.max";
+pub const CPU_MAX_FILENAME: &str = "cpu.max";
+pub const TASK_DIRNAME: &str = "task";
+pub const SELF_LINKNAME: &str = "self";
+pub const FD_DIRNAME: &str = "fd";
+pub const LIMITS_FILENAME: &str = "limits";
+pub const PASSWD_FILENAME: &str = "passwd";
+pub const OS_FILENAME: &str = "os-release";
 
+/// Where the /proc, /etc and /sys trees are read from,
+/// e.g. /host/proc in a sidecar container or a fixture directory in tests.
+#[derive(Debug, Clone)]
+pub struct FsRoot {
+    proc_dir: String,
+    etc_dir: String,
+    sys_dir: String,
+}
+
+impl Default for FsRoot {
+    fn default() -> FsRoot {
+        FsRoot::new(PROC_DIR, ETC_DIR)
+    }
+}
+
+impl FsRoot {
+    pub fn new(proc_dir: &str, etc_dir: &str) -> FsRoot {
+        FsRoot {
+            proc_dir: with_trailing_slash(proc_dir),
+            etc_dir: with_trailing_slash(etc_dir),
+            sys_dir: String::from(SYS_DIR),
+        }
+    }
+
+    /// Reads /sys, where the cgroup limits are, from `sys_dir` instead.
+    pub fn with_sys_dir(mut self, sys_dir: &str) -> FsRoot {
+        self.sys_dir = with_trailing_slash(sys_dir);
+        self
+    }
+
+    pub fn proc_dir(&self) -> &str { &self.proc_dir }
+
+    pub fn proc_path(&self, file_name: &str) -> String {
+        format!("{}{}", self.proc_dir, file_name)
+    }
+
+    pub fn pid_path(&self, pid: u32, file_name: &str) -> String {
+        format!("{}{}/{}", self.proc_dir, pid, file_name)
+    }
 
+    pub fn task_path(&self, pid: u32, tid: u32, file_name: &str) -> String {
+        format!("{}{}/{}/{}/{}", self.proc_dir, pid, TASK_DIRNAME, tid, file_name)
+    }
+
+    pub fn etc_path(&self, file_name: &str) -> String {
+        format!("{}{}", self.etc_dir, file_name)
+    }
+
+    pub fn sys_path(&self, file_name: &str) -> String {
+        format!("{}{}", self.sys_dir, file_name)
+    }
+}
+
+fn with_trailing_slash(dir: &str) -> String {
+    if dir.ends_with('/') {
+        return dir.to_string();
+    }
+    format!("{}/", dir)
+}
+
+
+/// Counters of the processes line of /proc/stat.
 pub enum ProcessStates {
-    Total,
+    /// Forks since boot, not the number of processes that exist.
+    Forks,
+    /// Threads that are running or ready to run.
     Running,
+    /// Threads blocked waiting for I/O.
     Blocked,
 }
 impl ProcessStates {
     fn label(&self) -> &str {
         match self {
-            Self::Total => "processes",
-            Self::Blocked => "procs_blocked",
+            Self::Forks => "processes",
             Self::Running => "procs_running",
+            Self::Blocked => "procs_blocked",
         }
     }
 }
@@ -48,139 +139,994 @@ where P: AsRef<Path>, {
     Ok(io::BufReader::new(file).lines())
 }
 
-
-fn is_numeric(str: &str) -> bool {
+pub(crate) fn is_numeric(str: &str) -> bool {
     let is_numeric: Vec<bool> = str.chars().map(|c| c.is_numeric()).collect();
     !is_numeric.contains(&false)
 }
 
+pub(crate) fn read_to_string(path: &str) -> Result<String, ParserError> {
+    file_utils::read_file_to_string(path).map_err(|e| ParserError::io(path, e))
+}
+
+pub(crate) fn parse_value<T: FromStr>(path: &str, value: &str) -> Result<T, ParserError> {
+    value.parse().map_err(|_| ParserError::invalid(path, value))
+}
 
-pub fn get_operating_system() -> String {
+pub fn get_operating_system(root: &FsRoot) -> Result<String, ParserError> {
     let os_key = "PRETTY_NAME";
-    let os_release = file_utils::read_as_hashmap(OS_PATH, "=");
-    let os_release = os_release.unwrap();
-    let os_name = os_release.get(os_key);
-    let os_name = os_name.unwrap();
-    os_name.replace("\"", "")
-}
-
-pub fn get_kernel() -> String {
-    let file_path = format!("{}{}", PROC_DIR, VERSION_FILENAME);
-    let version_info = match fs::read_to_string(file_path) {
-        Ok(str) => str,
-        Err(_) => "Failed to read version file".to_string(),
+    let file_path = root.etc_path(OS_FILENAME);
+    let os_release = file_utils::read_as_hashmap(&file_path, "=")
+        .map_err(|e| ParserError::io(&file_path, e))?;
+    let os_name = os_release
+        .get(os_key)
+        .ok_or_else(|| ParserError::missing(&file_path, os_key))?;
+    Ok(os_name.replace('"', ""))
+}
+
+pub fn get_kernel(root: &FsRoot) -> Result<String, ParserError> {
+    let file_path = root.proc_path(VERSION_FILENAME);
+    let version_info = read_to_string(&file_path)?;
+    let version_info: Vec<&str> = version_info.split(' ').collect();
+    match version_info.get(2) {
+        Some(version) => Ok(version.to_string()),
+        None => Err(ParserError::missing(&file_path, "release")),
+    }
+}
+
+// numeric directory names, i.e. pids in /proc or tids in /proc/[pid]/task
+fn get_numeric_entries(dir: &str) -> Result<Vec<u32>, ParserError> {
+    let mut ids: Vec<u32> = vec![];
+    let paths = fs::read_dir(dir).map_err(|e| ParserError::io(dir, e))?;
+    for path in paths {
+        let path = path.map_err(|e| ParserError::io(dir, e))?;
+        let id = path.file_name();
+        let id = id.to_string_lossy();
+        if is_numeric(&id) {
+            ids.push(parse_value(dir, &id)?);
+        }
+    }
+    Ok(ids)
+}
+
+pub fn get_pids(root: &FsRoot) -> Result<Vec<u32>, ParserError> {
+    get_numeric_entries(root.proc_dir())
+}
+
+/// The pid of the reading process as the proc tree sees it, from the `self` link.
+/// Fails if the reader isn't part of the pid namespace the tree was mounted for.
+pub fn get_self_pid(root: &FsRoot) -> Result<u32, ParserError> {
+    let file_path = root.proc_path(SELF_LINKNAME);
+    let target = fs::read_link(&file_path).map_err(|e| ParserError::io(&file_path, e))?;
+    parse_value(&file_path, &target.to_string_lossy())
+}
+
+pub fn get_tids(root: &FsRoot, pid: u32) -> Result<Vec<u32>, ParserError> {
+    get_numeric_entries(&root.pid_path(pid, TASK_DIRNAME))
+}
+
+/// The fields of /proc/meminfo used by the monitor, in kB.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MemStats {
+    pub total: u64,
+    pub free: u64,
+    pub available: u64,
+    pub buffers: u64,
+    pub cached: u64,
+    pub shared: u64,
+    pub reclaimable: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+}
+
+pub fn get_mem_stats(root: &FsRoot) -> Result<MemStats, ParserError> {
+    let file_path = root.proc_path(MEM_FILENAME);
+    let meminfo = file_utils::read_as_hashmap(&file_path, ":")
+        .map_err(|e| ParserError::io(&file_path, e))?;
+
+    // values look like "8000000 kB"
+    let field = |key: &str| -> Result<Option<u64>, ParserError> {
+        match meminfo.get(key) {
+            Some(value) => {
+                let value = value.split_whitespace().next().unwrap_or("");
+                Ok(Some(parse_value(&file_path, value)?))
+            }
+            None => Ok(None),
+        }
     };
-    let version_info = version_info.split(" ");
-    let version_info: Vec<&str> = version_info.collect();
-    version_info[2].to_string()
+
+    let total = field("MemTotal")?.ok_or_else(|| ParserError::missing(&file_path, "MemTotal"))?;
+    let free = field("MemFree")?.unwrap_or(0);
+    let buffers = field("Buffers")?.unwrap_or(0);
+    let cached = field("Cached")?.unwrap_or(0);
+    Ok(MemStats {
+        total,
+        free,
+        // MemAvailable only exists since linux 3.14
+        available: field("MemAvailable")?.unwrap_or(free + buffers + cached),
+        buffers,
+        cached,
+        shared: field("Shmem")?.unwrap_or(0),
+        reclaimable: field("SReclaimable")?.unwrap_or(0),
+        swap_total: field("SwapTotal")?.unwrap_or(0),
+        swap_free: field("SwapFree")?.unwrap_or(0),
+    })
+}
+
+pub fn get_uptime(root: &FsRoot) -> Result<f64, ParserError> {
+    let file_path = root.proc_path(UPTIME_FILENAME);
+    let line = read_to_string(&file_path)?;
+    let uptime = line.split_whitespace().next().unwrap_or("");
+
+    parse_value(&file_path, uptime)
 }
 
-pub fn get_pids() -> Vec<u32> {
-    let mut pids: Vec<u32> = vec![];
-    let paths = fs::read_dir(PROC_DIR).unwrap();
-    for path in paths{
-        let pid = path.unwrap().file_name().to_str().unwrap().to_string();
-        if is_numeric(&pid) {
-            let pid = pid.parse::<u32>().unwrap();
-            pids.push(pid);
+/// The contents of /proc/loadavg.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LoadAvg {
+    /// Average number of runnable or uninterruptible tasks over 1, 5 and 15 minutes.
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+    /// Scheduling entities (threads) that are runnable right now.
+    pub runnable: u32,
+    /// Scheduling entities that exist.
+    pub total: u32,
+    /// The most recently created pid.
+    pub last_pid: u32,
+}
+
+pub fn get_load_avg(root: &FsRoot) -> Result<LoadAvg, ParserError> {
+    let file_path = root.proc_path(LOADAVG_FILENAME);
+    let line = read_to_string(&file_path)?;
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let field = |index: usize, name: &str| -> Result<&str, ParserError> {
+        fields.get(index).copied().ok_or_else(|| ParserError::missing(&file_path, name))
+    };
+    let (runnable, total) = field(3, "runnable/total")?
+        .split_once('/')
+        .ok_or_else(|| ParserError::invalid(&file_path, fields[3]))?;
+    Ok(LoadAvg {
+        one: parse_value(&file_path, field(0, "1 minute load")?)?,
+        five: parse_value(&file_path, field(1, "5 minute load")?)?,
+        fifteen: parse_value(&file_path, field(2, "15 minute load")?)?,
+        runnable: parse_value(&file_path, runnable)?,
+        total: parse_value(&file_path, total)?,
+        last_pid: parse_value(&file_path, field(4, "last pid")?)?,
+    })
+}
+
+/// One line of a /proc/pressure file.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PsiLine {
+    /// Percentage of the time stalled over the last 10, 60 and 300 seconds.
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    /// Microseconds stalled since boot.
+    pub total: u64,
+}
+
+/// Pressure stall information of /proc/pressure/{cpu,memory,io}.
+///
+/// See https://docs.kernel.org/accounting/psi.html
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PsiStats {
+    /// Time at least some tasks were stalled.
+    pub some: PsiLine,
+    /// Time all non-idle tasks were stalled at once. Kernels before 5.13 have no full line for cpu.
+    pub full: Option<PsiLine>,
+}
+
+pub fn parse_psi_line(file_path: &str, line: &str) -> Result<PsiLine, ParserError> {
+    let mut psi = PsiLine::default();
+    // the first word is some or full, then key=value pairs
+    for pair in line.split_whitespace().skip(1) {
+        let (key, value) = pair.split_once('=').ok_or_else(|| ParserError::invalid(file_path, pair))?;
+        match key {
+            "avg10" => psi.avg10 = parse_value(file_path, value)?,
+            "avg60" => psi.avg60 = parse_value(file_path, value)?,
+            "avg300" => psi.avg300 = parse_value(file_path, value)?,
+            "total" => psi.total = parse_value(file_path, value)?,
+            _ => {}
         }
     }
-    return pids
+    Ok(psi)
 }
 
-pub fn get_mem_utilization() -> (u64, u64) {
-    let file_path = format!("{}{}", PROC_DIR, MEM_FILENAME);
+/// `resource` is one of cpu, memory or io. The files are missing on kernels
+/// built without PSI, and can't be read when it's disabled with psi=0.
+pub fn get_psi_stats(root: &FsRoot, resource: &str) -> Result<PsiStats, ParserError> {
+    let file_path = root.proc_path(&format!("{}/{}", PRESSURE_DIRNAME, resource));
+    let contents = read_to_string(&file_path)?;
+    let mut some: Option<PsiLine> = None;
+    let mut full: Option<PsiLine> = None;
+    for line in contents.lines() {
+        if line.starts_with("some ") {
+            some = Some(parse_psi_line(&file_path, line)?);
+        } else if line.starts_with("full ") {
+            full = Some(parse_psi_line(&file_path, line)?);
+        }
+    }
+    let some = some.ok_or_else(|| ParserError::missing(&file_path, "some"))?;
+    Ok(PsiStats { some, full })
+}
 
-    let lines = file_utils::read_n_lines(&file_path, 2);
-    let lines = lines.unwrap();
+/// The cgroup v2 path of the process, e.g. `/system.slice/nginx.service`.
+/// Empty on hosts with only cgroup v1.
+pub fn get_cgroup(root: &FsRoot, pid: u32) -> Result<String, ParserError> {
+    let file_path = root.pid_path(pid, CGROUP_FILENAME);
+    let contents = read_to_string(&file_path)?;
+    // the v2 hierarchy has id 0 and no controllers
+    let path = contents
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .unwrap_or("");
+    Ok(path.trim().to_string())
+}
 
-    let mem_total:Vec<&str> = lines.get(0).unwrap().split_whitespace().collect();
-    let mem_free:Vec<&str> = lines.get(1).unwrap().split_whitespace().collect();
+/// Where the cgroup v2 hierarchy is mounted, `None` without cgroup v2.
+/// Hybrid setups mount it at /sys/fs/cgroup/unified next to the v1 controllers.
+pub fn get_cgroup_dir(root: &FsRoot) -> Option<String> {
+    [CGROUP_DIRNAME, CGROUP_HYBRID_DIRNAME]
+        .iter()
+        .map(|dir| root.sys_path(dir))
+        .find(|dir| fs::metadata(format!("{}/{}", dir, CGROUP_CONTROLLERS_FILENAME)).is_ok())
+}
 
-    let mem_total = mem_total[1].parse::<u64>().unwrap();
-    let mem_free = mem_free[1].parse::<u64>().unwrap();
+/// The limits set on a cgroup. `None` means unlimited, or that the controller
+/// isn't enabled for the cgroup.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CgroupLimits {
+    /// Bytes, from memory.max.
+    pub memory_max: Option<u64>,
+    /// CPUs worth of time, from the quota and period of cpu.max, e.g. 1.5.
+    pub cpu_max: Option<f64>,
+}
 
-    (mem_free, mem_total)
+pub fn get_cgroup_limits(root: &FsRoot, cgroup: &str) -> Result<CgroupLimits, ParserError> {
+    let cgroup_dir = match get_cgroup_dir(root) {
+        Some(dir) => format!("{}{}", dir, cgroup.trim_end_matches('/')),
+        None => return Ok(CgroupLimits::default()),
+    };
+    // the root cgroup and cgroups without the controller don't have the files
+    let read_limit = |file_name: &str| -> Result<Option<(String, String)>, ParserError> {
+        let file_path = format!("{}/{}", cgroup_dir, file_name);
+        match read_to_string(&file_path) {
+            Ok(contents) => Ok(Some((file_path, contents.trim().to_string()))),
+            Err(e) if e.is_vanished() => Ok(None),
+            Err(e) => Err(e),
+        }
+    };
 
+    let mut limits = CgroupLimits::default();
+    if let Some((file_path, value)) = read_limit(MEMORY_MAX_FILENAME)? {
+        if value != "max" {
+            limits.memory_max = Some(parse_value(&file_path, &value)?);
+        }
+    }
+    if let Some((file_path, value)) = read_limit(CPU_MAX_FILENAME)? {
+        let (quota, period) = value.split_once(' ').unwrap_or((&value, "100000"));
+        if quota != "max" {
+            let quota: f64 = parse_value(&file_path, quota)?;
+            let period: f64 = parse_value(&file_path, period)?;
+            if period > 0.0 {
+                limits.cpu_max = Some(quota / period);
+            }
+        }
+    }
+    Ok(limits)
 }
 
-pub fn get_uptime() -> f64 {
-    let file_path = format!("{}{}", PROC_DIR, UPTIME_FILENAME);
-    let line = fs::read_to_string(file_path).expect("Unable to read uptime file");
-    let uptime: Vec<&str> = line.split_whitespace().collect();
+/// One line of /proc/diskstats, counters since boot.
+///
+/// See https://www.kernel.org/doc/html/latest/admin-guide/iostats.html
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DiskStats {
+    pub major: u32,
+    pub minor: u32,
+    pub name: String,
+    pub reads: u64,
+    /// Sectors of 512 bytes, whatever the sector size of the device.
+    pub sectors_read: u64,
+    pub read_ms: u64,
+    pub writes: u64,
+    pub sectors_written: u64,
+    pub write_ms: u64,
+    pub in_progress: u64,
+    /// Milliseconds during which at least one I/O was in flight.
+    pub io_ms: u64,
+}
 
-    uptime[0].parse::<f64>().unwrap()
+pub fn parse_disk_stats(file_path: &str, line: &str) -> Result<DiskStats, ParserError> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let field = |index: usize| -> Result<&str, ParserError> {
+        match fields.get(index) {
+            Some(value) => Ok(value),
+            None => Err(ParserError::missing(file_path, &format!("field {}", index + 1))),
+        }
+    };
+    Ok(DiskStats {
+        major: parse_value(file_path, field(0)?)?,
+        minor: parse_value(file_path, field(1)?)?,
+        name: field(2)?.to_string(),
+        reads: parse_value(file_path, field(3)?)?,
+        sectors_read: parse_value(file_path, field(5)?)?,
+        read_ms: parse_value(file_path, field(6)?)?,
+        writes: parse_value(file_path, field(7)?)?,
+        sectors_written: parse_value(file_path, field(9)?)?,
+        write_ms: parse_value(file_path, field(10)?)?,
+        in_progress: parse_value(file_path, field(11)?)?,
+        io_ms: parse_value(file_path, field(12)?)?,
+    })
 }
 
-pub fn get_cpu_cnt() -> u32 {
-    let file_path = format!("{}{}", PROC_DIR, STAT_FILENAME);
-    let lines = file_utils::iter_lines(&file_path);
-    let lines = match lines {
-        Ok(l) => l,
-        Err(_) => panic!("Failed to read file {}", file_path),
+pub fn get_disk_stats(root: &FsRoot) -> Result<Vec<DiskStats>, ParserError> {
+    let file_path = root.proc_path(DISKSTATS_FILENAME);
+    let lines = file_utils::iter_lines(&file_path).map_err(|e| ParserError::io(&file_path, e))?;
+    let mut disks: Vec<DiskStats> = vec![];
+    for line in lines {
+        let line = line.map_err(|e| ParserError::io(&file_path, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        disks.push(parse_disk_stats(&file_path, &line)?);
+    }
+    Ok(disks)
+}
+
+/// One interface of /proc/net/dev, counters since the interface came up.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NetStats {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errors: u64,
+    pub tx_dropped: u64,
+}
+
+pub fn parse_net_stats(file_path: &str, line: &str) -> Result<NetStats, ParserError> {
+    let (name, counters) = line
+        .split_once(':')
+        .ok_or_else(|| ParserError::missing(file_path, "interface name"))?;
+    let fields: Vec<&str> = counters.split_whitespace().collect();
+    let field = |index: usize| -> Result<u64, ParserError> {
+        match fields.get(index) {
+            Some(value) => parse_value(file_path, value),
+            None => Err(ParserError::missing(file_path, &format!("field {} of {}", index + 1, name.trim()))),
+        }
+    };
+    // receive and transmit have 8 columns each
+    Ok(NetStats {
+        name: name.trim().to_string(),
+        rx_bytes: field(0)?,
+        rx_packets: field(1)?,
+        rx_errors: field(2)?,
+        rx_dropped: field(3)?,
+        tx_bytes: field(8)?,
+        tx_packets: field(9)?,
+        tx_errors: field(10)?,
+        tx_dropped: field(11)?,
+    })
+}
+
+pub fn get_net_stats(root: &FsRoot) -> Result<Vec<NetStats>, ParserError> {
+    let file_path = root.proc_path(NET_DEV_FILENAME);
+    let lines = file_utils::iter_lines(&file_path).map_err(|e| ParserError::io(&file_path, e))?;
+    let mut interfaces: Vec<NetStats> = vec![];
+    // the first two lines are the table header
+    for line in lines.skip(2) {
+        let line = line.map_err(|e| ParserError::io(&file_path, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        interfaces.push(parse_net_stats(&file_path, &line)?);
+    }
+    Ok(interfaces)
+}
+
+/// One line of /proc/net/tcp, tcp6, udp or udp6.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InetSocketStats {
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    /// The kernel's TCP state, e.g. 0x0A for LISTEN. UDP sockets use 0x07 unless connected.
+    pub state: u8,
+    pub uid: u32,
+    pub inode: u64,
+}
+
+// an address like 0100007F:1F90, the address in the kernel's byte order and the port in hex
+fn parse_socket_addr(file_path: &str, addr: &str) -> Result<SocketAddr, ParserError> {
+    let invalid = || ParserError::invalid(file_path, addr);
+    let (ip, port) = addr.split_once(':').ok_or_else(invalid)?;
+    let port = u16::from_str_radix(port, 16).map_err(|_| invalid())?;
+    // printed as 32 bit words in the byte order of the host
+    let mut bytes: Vec<u8> = Vec::with_capacity(16);
+    for i in (0..ip.len()).step_by(8) {
+        let word = ip.get(i..i + 8).ok_or_else(invalid)?;
+        let word = u32::from_str_radix(word, 16).map_err(|_| invalid())?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    let ip = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])),
+        16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&bytes);
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return Err(invalid()),
     };
+    Ok(SocketAddr::new(ip, port))
+}
 
-    let mut cpu_cnt = 0;
+pub fn parse_inet_socket(file_path: &str, line: &str) -> Result<InetSocketStats, ParserError> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let field = |index: usize, name: &str| -> Result<&str, ParserError> {
+        fields.get(index).copied().ok_or_else(|| ParserError::missing(file_path, name))
+    };
+    let state = field(3, "st")?;
+    Ok(InetSocketStats {
+        local: parse_socket_addr(file_path, field(1, "local_address")?)?,
+        remote: parse_socket_addr(file_path, field(2, "rem_address")?)?,
+        state: u8::from_str_radix(state, 16).map_err(|_| ParserError::invalid(file_path, state))?,
+        uid: parse_value(file_path, field(7, "uid")?)?,
+        inode: parse_value(file_path, field(9, "inode")?)?,
+    })
+}
+
+/// The sockets of one of the /proc/net/tcp, tcp6, udp or udp6 tables.
+pub fn get_inet_sockets(root: &FsRoot, file_name: &str) -> Result<Vec<InetSocketStats>, ParserError> {
+    let file_path = root.proc_path(file_name);
+    let lines = file_utils::iter_lines(&file_path).map_err(|e| ParserError::io(&file_path, e))?;
+    let mut sockets: Vec<InetSocketStats> = vec![];
+    // the first line is the table header
+    for line in lines.skip(1) {
+        let line = line.map_err(|e| ParserError::io(&file_path, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        sockets.push(parse_inet_socket(&file_path, &line)?);
+    }
+    Ok(sockets)
+}
+
+/// The ephemeral ports the kernel picks from for sockets that aren't bound explicitly,
+/// from /proc/sys/net/ipv4/ip_local_port_range.
+pub fn get_local_port_range(root: &FsRoot) -> Result<(u16, u16), ParserError> {
+    let file_path = root.proc_path(LOCAL_PORT_RANGE_FILENAME);
+    let range = read_to_string(&file_path)?;
+    let mut ports = range.split_whitespace();
+    let mut port = |name: &str| -> Result<u16, ParserError> {
+        let value = ports.next().ok_or_else(|| ParserError::missing(&file_path, name))?;
+        parse_value(&file_path, value)
+    };
+    Ok((port("first port")?, port("last port")?))
+}
+
+// __SO_ACCEPTCON, set on sockets that listen for connections
+const UNIX_ACCEPTCON_FLAG: u32 = 0x10000;
+// SS_CONNECTED in the St column
+const UNIX_CONNECTED_STATE: u32 = 3;
+
+/// One line of /proc/net/unix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnixSocketStats {
+    pub listening: bool,
+    pub connected: bool,
+    /// 1 for stream, 2 for datagram and 5 for seqpacket sockets.
+    pub socket_type: u16,
+    pub inode: u64,
+    /// The bound path, starting with @ for abstract sockets, empty for unbound sockets.
+    pub path: String,
+}
+
+pub fn parse_unix_socket(file_path: &str, line: &str) -> Result<UnixSocketStats, ParserError> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let field = |index: usize, name: &str| -> Result<&str, ParserError> {
+        fields.get(index).copied().ok_or_else(|| ParserError::missing(file_path, name))
+    };
+    let hex = |index: usize, name: &str| -> Result<u32, ParserError> {
+        let value = field(index, name)?;
+        u32::from_str_radix(value, 16).map_err(|_| ParserError::invalid(file_path, value))
+    };
+    Ok(UnixSocketStats {
+        listening: hex(3, "Flags")? & UNIX_ACCEPTCON_FLAG != 0,
+        socket_type: hex(4, "Type")? as u16,
+        connected: hex(5, "St")? == UNIX_CONNECTED_STATE,
+        inode: parse_value(file_path, field(6, "Inode")?)?,
+        // paths can contain spaces
+        path: fields.get(7..).map(|path| path.join(" ")).unwrap_or_default(),
+    })
+}
+
+pub fn get_unix_sockets(root: &FsRoot) -> Result<Vec<UnixSocketStats>, ParserError> {
+    let file_path = root.proc_path(NET_UNIX_FILENAME);
+    let lines = file_utils::iter_lines(&file_path).map_err(|e| ParserError::io(&file_path, e))?;
+    let mut sockets: Vec<UnixSocketStats> = vec![];
+    // the first line is the table header
+    for line in lines.skip(1) {
+        let line = line.map_err(|e| ParserError::io(&file_path, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        sockets.push(parse_unix_socket(&file_path, &line)?);
+    }
+    Ok(sockets)
+}
+
+/// One cpu line of /proc/stat, in jiffies.
+///
+/// See https://www.kernel.org/doc/html/latest/filesystems/proc.html#miscellaneous-kernel-statistics-in-proc-stat
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+    pub guest: u64,
+    pub guest_nice: u64,
+}
+
+impl CpuTimes {
+    /// All jiffies, guest time is already accounted for in user and nice.
+    pub fn total(&self) -> u64 {
+        self.user + self.nice + self.system + self.idle
+            + self.iowait + self.irq + self.softirq + self.steal
+    }
+
+    pub fn idle_jiffies(&self) -> u64 {
+        self.idle + self.iowait
+    }
+
+    pub fn active_jiffies(&self) -> u64 {
+        self.total() - self.idle_jiffies()
+    }
+}
+
+fn parse_cpu_times(file_path: &str, line: &str) -> Result<CpuTimes, ParserError> {
+    let mut jiffies: Vec<u64> = vec![];
+    for str in line.split_whitespace().skip(1) {
+        jiffies.push(parse_value(file_path, str)?);
+    }
+    // older kernels report fewer columns
+    let field = |i: usize| jiffies.get(i).copied().unwrap_or(0);
+    Ok(CpuTimes {
+        user: field(0),
+        nice: field(1),
+        system: field(2),
+        idle: field(3),
+        iowait: field(4),
+        irq: field(5),
+        softirq: field(6),
+        steal: field(7),
+        guest: field(8),
+        guest_nice: field(9),
+    })
+}
+
+pub fn get_cpu_times(root: &FsRoot) -> Result<CpuTimes, ParserError> {
+    let file_path = root.proc_path(STAT_FILENAME);
+    let ut_line = file_utils::read_n_lines(&file_path, 1)
+        .map_err(|e| ParserError::io(&file_path, e))?;
+    parse_cpu_times(&file_path, &ut_line[0])
+}
+
+pub fn get_core_times(root: &FsRoot) -> Result<Vec<CpuTimes>, ParserError> {
+    let file_path = root.proc_path(STAT_FILENAME);
+    let lines = file_utils::iter_lines(&file_path).map_err(|e| ParserError::io(&file_path, e))?;
+
+    // one entry per cpuN line, in core order
+    let mut cores: Vec<CpuTimes> = vec![];
     for line in lines {
-        let line = line.unwrap();
-        if line.contains("cpu") {
-            cpu_cnt += 1;
-        }
-    }
-    match cpu_cnt {
-        0 => 0,
-        1 => 1,
-        _ => cpu_cnt - 1,
-    }
-}
-
-pub fn get_cpu_utilization() -> (u64, u64) {
-    // see https://www.linuxhowtos.org/System/procstat.htm
-    let mut cpu_utilization: Vec<u64> = vec![];
-
-    let file_path = format!("{}{}", PROC_DIR, STAT_FILENAME);
-    let ut_line = file_utils::read_n_lines(&file_path, 1);
-    let ut_line = ut_line.unwrap();
-    let ut_line = ut_line[0].to_string();
-    let utilization = ut_line.split_whitespace();
-    for str in utilization {
-        if is_numeric(str) {
-            let jiffy: u64 = match str.parse() {
-                Ok(num) => num,
-                Err(_) => panic!("Invalid jiffy string")
-            };
-            cpu_utilization.push(jiffy);
-        }
-    }
-    // (return active jiffies, idle jiffies)
-    (
-        cpu_utilization[0] + cpu_utilization[1] + cpu_utilization[2],
-        cpu_utilization[3] + cpu_utilization[4]
-    )
-}
-
-/*
-pub fn get_processes(process_type: ProcessStates) -> u32 {
-    let file_path = format!("{}{}", PROC_DIR, STAT_FILENAME);
-    let value = get_value(&process_type.label(), &file_path);
-    let value: u32 = match value.parse() {
-        Ok(num) => num,
-        Err(_) => panic!("Failed conver {} to u32", value),
+        let line = line.map_err(|e| ParserError::io(&file_path, e))?;
+        let label = line.split_whitespace().next().unwrap_or("");
+        if label.len() > 3 && label.starts_with("cpu") && is_numeric(&label[3..]) {
+            cores.push(parse_cpu_times(&file_path, &line)?);
+        }
+    }
+    Ok(cores)
+}
+
+/// The fields of /proc/[pid]/stat, indexed like proc(5) minus one.
+/// The command name is kept in one piece even if it contains spaces or parentheses.
+/// A truncated read without both parentheses around the command name is an error.
+pub fn parse_pid_stat(file_path: &str, stat: &str) -> Result<Vec<String>, ParserError> {
+    let comm_start = stat.find('(').ok_or_else(|| ParserError::missing(file_path, "("))?;
+    let comm_end = stat.rfind(')').ok_or_else(|| ParserError::missing(file_path, ")"))?;
+    if comm_end < comm_start {
+        return Err(ParserError::invalid(file_path, stat));
+    }
+
+    let mut fields: Vec<String> = vec![];
+    fields.push(stat[..comm_start].trim().to_string());
+    fields.push(stat[comm_start..=comm_end].to_string());
+    for field in stat[comm_end + 1..].split_whitespace() {
+        fields.push(field.to_string());
+    }
+    Ok(fields)
+}
+
+/// The fields of /proc/[pid]/stat or /proc/[pid]/task/[tid]/stat used by the monitor.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PidStat {
+    pub pid: u32,
+    /// The command name without the parentheses.
+    pub comm: String,
+    pub state: String,
+    pub ppid: u32,
+    /// Jiffies spent in user mode.
+    pub utime: u64,
+    /// Jiffies spent in kernel mode.
+    pub stime: u64,
+    /// From 19 (lowest priority) to -20 (highest priority).
+    pub nice: i32,
+    pub num_threads: u32,
+    /// Jiffies after system boot the process started at.
+    pub start_time: u64,
+}
+
+impl PidStat {
+    pub fn parse(file_path: &str, stat: &str) -> Result<PidStat, ParserError> {
+        let fields = parse_pid_stat(file_path, stat)?;
+        let field = |index: usize| -> Result<&str, ParserError> {
+            match fields.get(index) {
+                Some(value) => Ok(value),
+                None => Err(ParserError::missing(file_path, &format!("field {}", index + 1))),
+            }
+        };
+        let comm = field(1)?;
+        Ok(PidStat {
+            pid: parse_value(file_path, field(0)?)?,
+            comm: comm.trim_start_matches('(').trim_end_matches(')').to_string(),
+            state: field(2)?.to_string(),
+            ppid: parse_value(file_path, field(3)?)?,
+            utime: parse_value(file_path, field(13)?)?,
+            stime: parse_value(file_path, field(14)?)?,
+            nice: parse_value(file_path, field(18)?)?,
+            num_threads: parse_value(file_path, field(19)?)?,
+            start_time: parse_value(file_path, field(21)?)?,
+        })
+    }
+}
+
+pub fn get_pid_stat(root: &FsRoot, pid: u32) -> Result<PidStat, ParserError> {
+    let file_path = root.pid_path(pid, STAT_FILENAME);
+    let stat = read_to_string(&file_path)?;
+    PidStat::parse(&file_path, &stat)
+}
+
+pub fn get_task_stat(root: &FsRoot, pid: u32, tid: u32) -> Result<PidStat, ParserError> {
+    let file_path = root.task_path(pid, tid, STAT_FILENAME);
+    let stat = read_to_string(&file_path)?;
+    PidStat::parse(&file_path, &stat)
+}
+
+/// The fields of /proc/[pid]/io used by the monitor, in bytes or system calls.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct IoStats {
+    /// Bytes fetched from the storage layer.
+    pub read_bytes: u64,
+    /// Bytes sent to the storage layer.
+    pub write_bytes: u64,
+    /// Number of read system calls.
+    pub syscr: u64,
+    /// Number of write system calls.
+    pub syscw: u64,
+    /// Written bytes that never reached the storage, e.g. of a truncated file.
+    pub cancelled_write_bytes: u64,
+}
+
+/// Only readable by the owner of the process or root, and only
+/// if the kernel was built with task I/O accounting.
+pub fn get_io_stats(root: &FsRoot, pid: u32) -> Result<IoStats, ParserError> {
+    let file_path = root.pid_path(pid, IO_FILENAME);
+    let io = file_utils::read_as_hashmap(&file_path, ":")
+        .map_err(|e| ParserError::io(&file_path, e))?;
+    let field = |name: &str| -> Result<u64, ParserError> {
+        let value = io.get(name).ok_or_else(|| ParserError::missing(&file_path, name))?;
+        parse_value(&file_path, value)
     };
-    value
+    Ok(IoStats {
+        read_bytes: field("read_bytes")?,
+        write_bytes: field("write_bytes")?,
+        syscr: field("syscr")?,
+        syscw: field("syscw")?,
+        cancelled_write_bytes: field("cancelled_write_bytes")?,
+    })
+}
+
+/// The open file descriptors of `pid`. Only readable for your own processes unless running as root.
+pub fn get_fds(root: &FsRoot, pid: u32) -> Result<Vec<u32>, ParserError> {
+    get_numeric_entries(&root.pid_path(pid, FD_DIRNAME))
+}
+
+/// What the file descriptor `fd` of `pid` points to, e.g. `/var/log/syslog` or `socket:[12345]`.
+pub fn get_fd_target(root: &FsRoot, pid: u32, fd: u32) -> Result<String, ParserError> {
+    let file_path = format!("{}/{}", root.pid_path(pid, FD_DIRNAME), fd);
+    let target = fs::read_link(&file_path).map_err(|e| ParserError::io(&file_path, e))?;
+    Ok(target.to_string_lossy().into_owned())
+}
+
+/// The soft limit on open files of `pid` from /proc/[pid]/limits, `None` if unlimited.
+pub fn get_open_files_limit(root: &FsRoot, pid: u32) -> Result<Option<u64>, ParserError> {
+    let file_path = root.pid_path(pid, LIMITS_FILENAME);
+    let contents = read_to_string(&file_path)?;
+    // Limit  Soft Limit  Hard Limit  Units, the limit name has spaces in it
+    let soft_limit = contents
+        .lines()
+        .find_map(|line| line.strip_prefix("Max open files"))
+        .and_then(|values| values.split_whitespace().next())
+        .ok_or_else(|| ParserError::missing(&file_path, "Max open files"))?;
+    match soft_limit {
+        "unlimited" => Ok(None),
+        limit => Ok(Some(parse_value(&file_path, limit)?)),
+    }
 }
-*/
 
-pub fn get_processes(process_type: ProcessStates) -> u32 {
-    let file_path = format!("{}{}", PROC_DIR, STAT_FILENAME);
-    let stat = file_utils::read_as_hashmap(&file_path, " ");
-    let stat = stat.unwrap();
-    let value = stat.get(process_type.label());
-    let value = value.unwrap();
-    match value.parse() {
-        Ok(num) => num,
-        Err(e) => panic!("Failed to convert {} to u32: {}", value, e),
+/// Parses a list of CPUs like `Cpus_allowed_list` in /proc/[pid]/status, e.g. `0-3,8,10-11`.
+pub fn parse_cpu_list(list: &str) -> Result<Vec<usize>, String> {
+    let mut cpus: Vec<usize> = vec![];
+    for range in list.split(',').map(str::trim).filter(|range| !range.is_empty()) {
+        let invalid = || format!("Invalid CPU list {}", list);
+        match range.split_once('-') {
+            Some((first, last)) => {
+                let first: usize = first.trim().parse().map_err(|_| invalid())?;
+                let last: usize = last.trim().parse().map_err(|_| invalid())?;
+                if first > last {
+                    return Err(invalid());
+                }
+                cpus.extend(first..=last);
+            }
+            None => cpus.push(range.parse().map_err(|_| invalid())?),
+        }
     }
+    Ok(cpus)
+}
 
+pub fn get_processes(root: &FsRoot, process_type: ProcessStates) -> Result<u64, ParserError> {
+    let file_path = root.proc_path(STAT_FILENAME);
+    let stat = file_utils::read_as_hashmap(&file_path, " ")
+        .map_err(|e| ParserError::io(&file_path, e))?;
+    let value = stat
+        .get(process_type.label())
+        .ok_or_else(|| ParserError::missing(&file_path, process_type.label()))?;
+    parse_value(&file_path, value)
 }
 
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::*;
+
+    /// The cgroup of the fixture process 42, a docker container.
+    pub const WORKER_CGROUP: &str =
+        "/system.slice/docker-3f4e1a2b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f9012345678abcdef.scope";
+
+    pub fn fixture_root() -> FsRoot {
+        FsRoot::new(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/proc"),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/etc"),
+        )
+        .with_sys_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/sys"))
+    }
+
+    /// The fixture processes 1, 2 and 42, read 1000.5 seconds after boot.
+    pub fn fixture_processes() -> Vec<crate::process::Process> {
+        let root = fixture_root();
+        [1, 2, 42]
+            .iter()
+            .map(|pid| crate::process::Process::new(&root, *pid, 1000.5).unwrap())
+            .collect()
+    }
+
+    /// A /proc without the optional files like diskstats, with a process 7 whose status is malformed
+    /// and a thread 9 of process 1 whose stat is truncated.
+    pub fn minimal_root() -> FsRoot {
+        FsRoot::new(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/minimal/proc"),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/etc"),
+        )
+    }
+
+    #[test]
+    fn fixture_os_and_kernel() {
+        let root = fixture_root();
+        assert_eq!(get_operating_system(&root).unwrap(), "Fixture Linux 1.0");
+        assert_eq!(get_kernel(&root).unwrap(), "5.15.0-fixture");
+    }
+
+    #[test]
+    fn fixture_pids() {
+        let mut pids = get_pids(&fixture_root()).unwrap();
+        pids.sort_unstable();
+        assert_eq!(pids, vec![1, 2, 42]);
+    }
+
+    #[test]
+    fn fixture_meminfo() {
+        let mem = get_mem_stats(&fixture_root()).unwrap();
+        assert_eq!(mem.total, 8000000);
+        assert_eq!(mem.available, 5000000);
+        assert_eq!(mem.cached, 3000000);
+        assert_eq!(mem.shared, 100000);
+        assert_eq!(mem.swap_free, 1500000);
+    }
+
+    #[test]
+    fn parse_cpu_times_short_line() {
+        let times = parse_cpu_times("stat", "cpu  10 20 30 40").unwrap();
+        assert_eq!(times.idle, 40);
+        assert_eq!(times.steal, 0);
+        assert_eq!(times.total(), 100);
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        let root = FsRoot::new("/nonexistent/proc", "/nonexistent/etc");
+        assert!(get_uptime(&root).unwrap_err().is_vanished());
+        assert!(get_operating_system(&root).is_err());
+    }
+
+    #[test]
+    fn parse_pid_stat_comm_with_spaces() {
+        let stat = parse_pid_stat("/proc/7/stat", "7 (Web (Content)) S 1 7 7 0 -1 0 0 0 0 0 11 12").unwrap();
+        assert_eq!(stat[0], "7");
+        assert_eq!(stat[1], "(Web (Content))");
+        assert_eq!(stat[2], "S");
+        assert_eq!(stat[13], "11");
+    }
+
+    #[test]
+    fn truncated_pid_stat_is_an_error() {
+        assert!(matches!(parse_pid_stat("/proc/7/stat", ""), Err(ParserError::MissingField { .. })));
+        assert!(matches!(parse_pid_stat("/proc/7/stat", "1 ) ("), Err(ParserError::InvalidValue { .. })));
+        assert!(PidStat::parse("/proc/7/stat", "7 (bash").is_err());
+    }
+
+    #[test]
+    fn fixture_pid_stat() {
+        let stat = get_pid_stat(&fixture_root(), 42).unwrap();
+        assert_eq!(stat.comm, "python3");
+        assert_eq!(stat.ppid, 1);
+        assert_eq!(stat.utime + stat.stime, 1000);
+        assert_eq!(stat.nice, 10);
+        assert_eq!(stat.num_threads, 2);
+        assert_eq!(stat.start_time, 50000);
+    }
+
+    #[test]
+    fn fixture_io() {
+        let root = fixture_root();
+        let io = get_io_stats(&root, 42).unwrap();
+        assert_eq!(io.read_bytes, 4096000);
+        assert_eq!(io.syscw, 300);
+        assert_eq!(io.cancelled_write_bytes, 8192);
+        assert!(get_io_stats(&root, 1).is_err());
+    }
+
+    #[test]
+    fn fixture_fds() {
+        let root = fixture_root();
+        let mut fds = get_fds(&root, 42).unwrap();
+        fds.sort_unstable();
+        assert_eq!(fds, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(get_fd_target(&root, 42, 3).unwrap(), "socket:[2001]");
+        assert_eq!(get_open_files_limit(&root, 42).unwrap(), Some(6));
+        assert_eq!(get_open_files_limit(&root, 2).unwrap(), None);
+        assert!(get_fds(&root, 1).is_err());
+    }
+
+    #[test]
+    fn fixture_sockets() {
+        let root = fixture_root();
+        let tcp = get_inet_sockets(&root, NET_TCP_FILENAME).unwrap();
+        assert_eq!(tcp[0].local, "0.0.0.0:8080".parse().unwrap());
+        assert_eq!(tcp[0].state, 0x0A);
+        assert_eq!(tcp[1].remote, "127.0.0.1:50000".parse().unwrap());
+        assert_eq!((tcp[1].uid, tcp[1].inode), (1000, 2002));
+        let tcp6 = get_inet_sockets(&root, NET_TCP6_FILENAME).unwrap();
+        assert_eq!(tcp6[1].local, "[::1]:8081".parse().unwrap());
+        // IPv6 is disabled in the fixture
+        assert!(get_inet_sockets(&root, NET_UDP6_FILENAME).is_err());
+
+        let unix = get_unix_sockets(&root).unwrap();
+        assert!(unix[0].listening);
+        assert_eq!(unix[0].path, "/run/systemd/private");
+        assert_eq!(unix[1].path, "");
+        assert!(unix[1].connected);
+        assert!(parse_unix_socket("unix", "0000000000000000: 00000002 00000000 00010000").is_err());
+    }
+
+    #[test]
+    fn fixture_diskstats() {
+        let disks = get_disk_stats(&fixture_root()).unwrap();
+        let sda = disks.iter().find(|disk| disk.name == "sda").unwrap();
+        assert_eq!(sda.reads, 10000);
+        assert_eq!(sda.sectors_written, 400000);
+        assert_eq!(sda.io_ms, 50000);
+        assert!(parse_disk_stats("diskstats", "8 0 sda 1 2 3").is_err());
+    }
+
+    #[test]
+    fn fixture_net_dev() {
+        let interfaces = get_net_stats(&fixture_root()).unwrap();
+        assert_eq!(interfaces.len(), 2);
+        let eth0 = &interfaces[1];
+        assert_eq!(eth0.name, "eth0");
+        assert_eq!(eth0.rx_bytes, 90000000);
+        assert_eq!(eth0.rx_dropped, 7);
+        assert_eq!(eth0.tx_packets, 30000);
+        assert_eq!(eth0.tx_dropped, 2);
+    }
+
+    #[test]
+    fn fixture_loadavg() {
+        let load = get_load_avg(&fixture_root()).unwrap();
+        assert_eq!(load.one, 0.5);
+        assert_eq!(load.fifteen, 1.25);
+        assert_eq!(load.runnable, 2);
+        assert_eq!(load.total, 345);
+        assert_eq!(load.last_pid, 4321);
+    }
+
+    #[test]
+    fn fixture_pressure() {
+        let root = fixture_root();
+        let cpu = get_psi_stats(&root, "cpu").unwrap();
+        assert_eq!(cpu.some.avg10, 1.5);
+        assert_eq!(cpu.some.total, 123456789);
+        assert!(cpu.full.is_none());
+        let io = get_psi_stats(&root, "io").unwrap();
+        assert_eq!(io.full.unwrap().avg60, 7.0);
+    }
+
+    #[test]
+    fn fixture_cgroups() {
+        let root = fixture_root();
+        assert_eq!(get_cgroup(&root, 42).unwrap(), WORKER_CGROUP);
+        assert_eq!(get_cgroup(&root, 2).unwrap(), "/");
+        let limits = get_cgroup_limits(&root, WORKER_CGROUP).unwrap();
+        assert_eq!(limits.memory_max, Some(536870912));
+        assert_eq!(limits.cpu_max, Some(1.5));
+        assert_eq!(get_cgroup_limits(&root, "/init.scope").unwrap(), CgroupLimits::default());
+        assert_eq!(get_cgroup_limits(&root, "/").unwrap(), CgroupLimits::default());
+    }
+
+    #[test]
+    fn cpu_lists() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11").unwrap(), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list("2").unwrap(), vec![2]);
+        assert!(parse_cpu_list("3-1").is_err());
+        assert!(parse_cpu_list("a").is_err());
+    }
+
+    #[test]
+    fn short_pid_stat_is_an_error() {
+        assert!(PidStat::parse("stat", "7 (x) S 1 7").is_err());
+    }
+
+    #[test]
+    fn fixture_stat() {
+        let root = fixture_root();
+        let times = get_cpu_times(&root).unwrap();
+        assert_eq!(times.user, 4000);
+        assert_eq!(times.iowait, 500);
+        assert_eq!(times.steal, 10);
+        assert_eq!(times.total(), 36710);
+        assert_eq!(times.active_jiffies(), 6210);
+        assert_eq!(get_core_times(&root).unwrap().len(), 2);
+        assert_eq!(get_processes(&root, ProcessStates::Running).unwrap(), 2);
+        assert_eq!(get_processes(&root, ProcessStates::Forks).unwrap(), 5000);
+        assert_eq!(get_processes(&root, ProcessStates::Blocked).unwrap(), 1);
+        assert_eq!(get_uptime(&root).unwrap(), 1000.5);
+    }
+}
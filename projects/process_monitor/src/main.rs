@@ -1,31 +1,68 @@
-mod linux_parser;
-mod process;
-mod file_utils;
-mod system;
 mod terminal;
-mod format;
-mod cpu;
-mod memory;
+mod config;
+mod tui;
+mod batch;
 
-use crate::system::System;
+use crate::config::{Config, OutputFormat};
+use process_monitor::{ContainerNames, System, ThreadScope};
 
-use std::{thread, time::Duration};
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal};
+use std::{env, thread, time::Duration};
 
 fn wait(millis: u64) {
     thread::sleep(Duration::from_millis(millis));
 }
 
 fn main() {
-    
-    let mut sys = System::new();
+    let config = Config::new(env::args()).unwrap_or_else(|err| {
+        eprintln!("Problem parsing arguments: {}", err);
+        std::process::exit(1);
+    });
+
+    let mut sys = System::new(config.fs_root()).unwrap_or_else(|err| {
+        eprintln!("Failed to read the system: {}", err);
+        std::process::exit(1);
+    });
+    sys.set_sort(config.sort_key, config.sort_order);
+    sys.set_filter(config.filter.clone());
+    sys.set_show_loopback(config.show_loopback);
+    if let Some(path) = &config.container_names {
+        let names = ContainerNames::from_file(path).unwrap_or_else(|err| {
+            eprintln!("Failed to read the container names: {}", err);
+            std::process::exit(1);
+        });
+        sys.set_container_resolver(Box::new(names));
+    }
+    if config.threads {
+        sys.set_thread_scope(ThreadScope::All);
+    }
     wait(100);
-    
-    loop {
-        terminal::clear_screen();
-        sys.refresh();
-        //println!("{:#?}", sys);
-        sys.print(10);
 
-        wait(2000);
+    if !config.batch && config.format == OutputFormat::Text && io::stdout().is_terminal() {
+        if let Err(err) = tui::run(sys, config.delay_millis, &config) {
+            eprintln!("Application error: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let result = match &config.output {
+        Some(path) => match File::create(path) {
+            Ok(file) => batch::run(sys, &config, &mut BufWriter::new(file)),
+            Err(err) => {
+                eprintln!("Failed to create {}: {}", path, err);
+                std::process::exit(1);
+            }
+        },
+        None => batch::run(sys, &config, &mut io::stdout().lock()),
+    };
+    if let Err(err) = result {
+        // the reader went away, e.g. piped into head
+        if matches!(err.downcast_ref::<io::Error>(), Some(err) if err.kind() == io::ErrorKind::BrokenPipe) {
+            return;
+        }
+        eprintln!("Application error: {}", err);
+        std::process::exit(1);
     }
 }
@@ -1,7 +1,9 @@
 use std::fmt;
+use crate::error::ParserError;
 use crate::format;
-use crate::linux_parser;
+use crate::linux_parser::{self, FsRoot, MemStats};
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone, Copy)]
 pub enum MemScale {
     AsKiloBytes = 1,
@@ -20,10 +22,10 @@ impl MemScale {
     }
 }
 
+/// Memory and swap usage from /proc/meminfo.
 #[derive(Debug)]
 pub struct MemInfo {
-    total_mem: u64,
-    free_mem: u64,
+    stats: MemStats,
     scaling: MemScale,
 }
 
@@ -31,33 +33,89 @@ impl fmt::Display for MemInfo {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f, 
-            "- Total: {}, Free: {}  [{}]\n- Utilization: {}", 
-            self.total_mem,
-            self.free_mem,
+            "- Total: {}, Used: {}, Available: {}, Free: {}  [{}]\n\
+             - Buffers: {}, Cached: {}, Shared: {}  [{}]\n\
+             - Utilization: {}\n\
+             - Swap Total: {}, Swap Used: {}  [{}]\n\
+             - Swap: {}", 
+            self.scale(self.stats.total),
+            self.scale(self.used()),
+            self.scale(self.stats.available),
+            self.scale(self.stats.free),
             self.scaling.unit(),
-            format::bar(50, self.utilization())
+            self.scale(self.stats.buffers),
+            self.scale(self.cached()),
+            self.scale(self.stats.shared),
+            self.scaling.unit(),
+            format::bar(50, self.utilization()),
+            self.scale(self.stats.swap_total),
+            self.scale(self.swap_used()),
+            self.scaling.unit(),
+            format::bar(50, self.swap_utilization())
         )
     }
 }
 
 impl MemInfo {
-    pub fn new() -> MemInfo {
+    pub fn new(root: &FsRoot) -> Result<MemInfo, ParserError> {
         let scaling = MemScale::AsKiloBytes;
-        let (free_mem, total_mem) = linux_parser::get_mem_utilization();
-        let free_mem = free_mem / scaling as u64;
-        let total_mem = total_mem / scaling as u64;
+        let stats = linux_parser::get_mem_stats(root)?;
+
+        Ok(MemInfo { stats, scaling })
+    }
+
+    /// The raw values, in kB.
+    pub fn stats(&self) -> &MemStats { &self.stats }
 
-        MemInfo { total_mem, free_mem, scaling}
+    fn scale(&self, kb: u64) -> u64 {
+        kb / self.scaling as u64
+    }
+
+    /// Used memory in kB. Page cache and reclaimable slab can be dropped, so they don't count as used.
+    pub fn used(&self) -> u64 {
+        self.stats.total.saturating_sub(self.stats.available)
+    }
+
+    pub fn cached(&self) -> u64 {
+        self.stats.cached + self.stats.reclaimable
+    }
+
+    pub fn swap_used(&self) -> u64 {
+        self.stats.swap_total.saturating_sub(self.stats.swap_free)
     }
 
     pub fn utilization(&self) -> f64 {
-        1.0 - (self.free_mem as f64 / self.total_mem as f64)
+        // don't divide by zero
+        if self.stats.total == 0 {
+            return 0.0;
+        }
+        self.used() as f64 / self.stats.total as f64
+    }
+
+    pub fn swap_utilization(&self) -> f64 {
+        // swap is optional
+        if self.stats.swap_total == 0 {
+            return 0.0;
+        }
+        self.swap_used() as f64 / self.stats.swap_total as f64
     }
 
-    pub fn refresh(&mut self) {
-        let (free_mem, _) = linux_parser::get_mem_utilization();
-        let free_mem = free_mem / self.scaling as u64;
+    pub fn refresh(&mut self, root: &FsRoot) -> Result<(), ParserError> {
+        self.stats = linux_parser::get_mem_stats(root)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
 
-        self.free_mem = free_mem
+    #[test]
+    fn used_from_available() {
+        let mem = MemInfo::new(&linux_parser::tests::fixture_root()).unwrap();
+        assert_eq!(mem.used(), 3000000);
+        assert_eq!(mem.utilization(), 0.375);
+        assert_eq!(mem.swap_utilization(), 0.25);
     }
 }
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::ParserError;
+use crate::format;
+use crate::linux_parser::{self, FsRoot, NetStats};
+
+const LOOPBACK_NAME: &str = "lo";
+
+/// The traffic of one network interface between the last two refreshes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterfaceUsage {
+    pub name: String,
+    /// Bytes per second.
+    pub rx_rate: f64,
+    pub tx_rate: f64,
+    /// Packets per second.
+    pub rx_packet_rate: f64,
+    pub tx_packet_rate: f64,
+    /// Errors and dropped packets since the previous refresh.
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+}
+
+impl InterfaceUsage {
+    fn between(prev: &NetStats, current: &NetStats, seconds: f64) -> InterfaceUsage {
+        // counters restart from zero when an interface is recreated
+        let delta = |current: u64, prev: u64| current.saturating_sub(prev);
+        let per_second = |count: u64| {
+            // don't divide by zero
+            if seconds <= 0.0 {
+                return 0.0;
+            }
+            count as f64 / seconds
+        };
+        InterfaceUsage {
+            name: current.name.clone(),
+            rx_rate: per_second(delta(current.rx_bytes, prev.rx_bytes)),
+            tx_rate: per_second(delta(current.tx_bytes, prev.tx_bytes)),
+            rx_packet_rate: per_second(delta(current.rx_packets, prev.rx_packets)),
+            tx_packet_rate: per_second(delta(current.tx_packets, prev.tx_packets)),
+            rx_errors: delta(current.rx_errors, prev.rx_errors),
+            tx_errors: delta(current.tx_errors, prev.tx_errors),
+            rx_dropped: delta(current.rx_dropped, prev.rx_dropped),
+            tx_dropped: delta(current.tx_dropped, prev.tx_dropped),
+        }
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        self.name == LOOPBACK_NAME
+    }
+}
+
+// reads /proc/net/dev, None if it can't be read, e.g. under a custom proc root without it
+fn read_net_stats(root: &FsRoot) -> Result<Option<Vec<NetStats>>, ParserError> {
+    match linux_parser::get_net_stats(root) {
+        Ok(stats) => Ok(Some(stats)),
+        Err(ParserError::Io { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Network interface traffic from /proc/net/dev, between the last two refreshes.
+/// Without a readable /proc/net/dev there are no interfaces and the panel says so.
+#[derive(Debug)]
+pub struct Network {
+    prev_stats: HashMap<String, NetStats>,
+    current_stats: Vec<NetStats>,
+    prev_sampled_at: f64,
+    sampled_at: f64,
+    show_loopback: bool,
+    available: bool,
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.available {
+            return write!(f, "- Not available");
+        }
+        let interfaces = self.shown_usage();
+        if interfaces.is_empty() {
+            return write!(f, "- No interfaces");
+        }
+        for (i, interface) in interfaces.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "- {:<10} RX: {:>8}/s {:>7.0} pkt/s  TX: {:>8}/s {:>7.0} pkt/s  Errs: {}/{}  Drops: {}/{}",
+                interface.name,
+                format::format_bytes(interface.rx_rate),
+                interface.rx_packet_rate,
+                format::format_bytes(interface.tx_rate),
+                interface.tx_packet_rate,
+                interface.rx_errors,
+                interface.tx_errors,
+                interface.rx_dropped,
+                interface.tx_dropped,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Network {
+    pub fn new(root: &FsRoot) -> Result<Network, ParserError> {
+        let current_stats = read_net_stats(root)?;
+        let sampled_at = linux_parser::get_uptime(root)?;
+        // until the first refresh the rates are averages since boot
+        Ok(Network {
+            prev_stats: HashMap::new(),
+            available: current_stats.is_some(),
+            current_stats: current_stats.unwrap_or_default(),
+            prev_sampled_at: 0.0,
+            sampled_at,
+            show_loopback: true,
+        })
+    }
+
+    /// False if /proc/net/dev can't be read.
+    pub fn is_available(&self) -> bool { self.available }
+
+    /// Every interface, in the order of /proc/net/dev.
+    pub fn usage(&self) -> Vec<InterfaceUsage> {
+        let seconds = self.sampled_at - self.prev_sampled_at;
+        self.current_stats
+            .iter()
+            .map(|current| {
+                // an interface that showed up since the last refresh starts from zero
+                let prev = self.prev_stats.get(&current.name).cloned().unwrap_or_default();
+                InterfaceUsage::between(&prev, current, seconds)
+            })
+            .collect()
+    }
+
+    /// The interfaces shown in the panel, without loopback if it's hidden.
+    pub fn shown_usage(&self) -> Vec<InterfaceUsage> {
+        self.usage()
+            .into_iter()
+            .filter(|interface| self.show_loopback || !interface.is_loopback())
+            .collect()
+    }
+
+    pub fn set_show_loopback(&mut self, show: bool) {
+        self.show_loopback = show;
+    }
+
+    pub fn show_loopback(&self) -> bool { self.show_loopback }
+
+    pub fn refresh(&mut self, root: &FsRoot) -> Result<(), ParserError> {
+        let current_stats = read_net_stats(root)?;
+        let sampled_at = linux_parser::get_uptime(root)?;
+
+        self.available = current_stats.is_some();
+        let current_stats = current_stats.unwrap_or_default();
+        let prev_stats = std::mem::replace(&mut self.current_stats, current_stats);
+        self.prev_stats = prev_stats.into_iter().map(|interface| (interface.name.clone(), interface)).collect();
+        self.prev_sampled_at = self.sampled_at;
+        self.sampled_at = sampled_at;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn usage_between_samples() {
+        let prev = NetStats {
+            name: String::from("eth0"),
+            rx_bytes: 1000,
+            rx_packets: 10,
+            tx_bytes: 500,
+            rx_dropped: 4,
+            ..NetStats::default()
+        };
+        let current = NetStats {
+            rx_bytes: 5000,
+            rx_packets: 30,
+            tx_bytes: 2500,
+            rx_dropped: 5,
+            ..prev.clone()
+        };
+        let usage = InterfaceUsage::between(&prev, &current, 2.0);
+        assert_eq!(usage.rx_rate, 2000.0);
+        assert_eq!(usage.tx_rate, 1000.0);
+        assert_eq!(usage.rx_packet_rate, 10.0);
+        assert_eq!(usage.rx_dropped, 1);
+    }
+
+    #[test]
+    fn hide_loopback() {
+        let mut network = Network::new(&linux_parser::tests::fixture_root()).unwrap();
+        assert_eq!(network.shown_usage().len(), 2);
+        network.set_show_loopback(false);
+        let names: Vec<String> = network.shown_usage().into_iter().map(|interface| interface.name).collect();
+        assert_eq!(names, vec!["eth0"]);
+        assert!(!network.to_string().contains("lo "));
+    }
+
+    #[test]
+    fn without_net_dev() {
+        let root = linux_parser::tests::minimal_root();
+        let mut network = Network::new(&root).unwrap();
+        network.refresh(&root).unwrap();
+        assert!(!network.is_available());
+        assert!(network.usage().is_empty());
+        assert_eq!(network.to_string(), "- Not available");
+    }
+}
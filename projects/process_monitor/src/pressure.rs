@@ -0,0 +1,108 @@
+use std::fmt;
+
+use crate::error::ParserError;
+use crate::format;
+use crate::linux_parser::{self, FsRoot, PsiLine, PsiStats};
+
+const MICROSECONDS_PER_SECOND: u64 = 1_000_000;
+
+// reads one pressure file, None if the kernel has no PSI
+fn read_psi(root: &FsRoot, resource: &str) -> Result<Option<PsiStats>, ParserError> {
+    match linux_parser::get_psi_stats(root, resource) {
+        Ok(stats) => Ok(Some(stats)),
+        // missing without CONFIG_PSI, EOPNOTSUPP when booted with psi=0
+        Err(ParserError::Io { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn format_psi_line(label: &str, line: &PsiLine) -> String {
+    format!(
+        "{} {:>6.2} {:>6.2} {:>6.2} total {}",
+        label,
+        line.avg10,
+        line.avg60,
+        line.avg300,
+        format::format_seconds(line.total / MICROSECONDS_PER_SECOND),
+    )
+}
+
+/// Pressure stall information from /proc/pressure: the share of time tasks
+/// waited for the CPU, memory or I/O. Every resource is `None` on kernels without PSI.
+#[derive(Debug)]
+pub struct Pressure {
+    cpu: Option<PsiStats>,
+    memory: Option<PsiStats>,
+    io: Option<PsiStats>,
+}
+
+impl fmt::Display for Pressure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.is_available() {
+            return write!(f, "- Not available");
+        }
+        // lined up with the values of the rows below
+        write!(f, "- {:<12}{:>6} {:>6} {:>6} [%]", "", "avg10", "avg60", "avg300")?;
+        for (label, stats) in [("CPU", &self.cpu), ("Memory", &self.memory), ("IO", &self.io)] {
+            write!(f, "\n- {:<7}", label)?;
+            match stats {
+                Some(stats) => {
+                    write!(f, "{}", format_psi_line("some", &stats.some))?;
+                    if let Some(full) = &stats.full {
+                        write!(f, "  {}", format_psi_line("full", full))?;
+                    }
+                }
+                None => write!(f, "not available")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Pressure {
+    pub fn new(root: &FsRoot) -> Result<Pressure, ParserError> {
+        Ok(Pressure {
+            cpu: read_psi(root, "cpu")?,
+            memory: read_psi(root, "memory")?,
+            io: read_psi(root, "io")?,
+        })
+    }
+
+    /// False on kernels built without PSI or booted with psi=0.
+    pub fn is_available(&self) -> bool {
+        self.cpu.is_some() || self.memory.is_some() || self.io.is_some()
+    }
+
+    pub fn cpu(&self) -> Option<&PsiStats> { self.cpu.as_ref() }
+
+    pub fn memory(&self) -> Option<&PsiStats> { self.memory.as_ref() }
+
+    pub fn io(&self) -> Option<&PsiStats> { self.io.as_ref() }
+
+    pub fn refresh(&mut self, root: &FsRoot) -> Result<(), ParserError> {
+        *self = Pressure::new(root)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn fixture_pressure() {
+        let pressure = Pressure::new(&linux_parser::tests::fixture_root()).unwrap();
+        assert!(pressure.is_available());
+        assert_eq!(pressure.memory().unwrap().full.unwrap().avg300, 0.01);
+        assert!(pressure.to_string().contains("- IO     some  12.00   8.50   4.00 total 00:01:30"));
+    }
+
+    #[test]
+    fn kernel_without_psi() {
+        // the minimal fixture has no pressure directory
+        let pressure = Pressure::new(&linux_parser::tests::minimal_root()).unwrap();
+        assert!(!pressure.is_available());
+        assert_eq!(pressure.to_string(), "- Not available");
+    }
+}
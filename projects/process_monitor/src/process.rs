@@ -1,190 +1,633 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fmt;
+use std::str::FromStr;
 
-use crate::linux_parser;
+use crate::container::{self, ContainerResolver};
+use crate::error::ParserError;
+use crate::fd;
+use crate::linux_parser::{self, FsRoot, IoStats, PidStat};
 use crate::file_utils;
 use crate::format;
 
 const RAM_LABEL_FILTER: &str = "VmData";
 const UID_LABEL_FILTER: &str = "Uid";
+const CPUS_ALLOWED_LABEL_FILTER: &str = "Cpus_allowed_list";
 
-#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
+/// Column the process list can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Pid,
+    User,
+    Cpu,
+    Ram,
+    Threads,
+    Nice,
+    IoRead,
+    IoWrite,
+    Fds,
+    Connections,
+    Uptime,
+    Command,
+}
+
+impl SortKey {
+    // in the order of the table columns
+    const ALL: [SortKey; 12] = [
+        SortKey::Pid,
+        SortKey::User,
+        SortKey::Cpu,
+        SortKey::Ram,
+        SortKey::Threads,
+        SortKey::Nice,
+        SortKey::IoRead,
+        SortKey::IoWrite,
+        SortKey::Fds,
+        SortKey::Connections,
+        SortKey::Uptime,
+        SortKey::Command,
+    ];
+
+    pub fn label(&self) -> &str {
+        match self {
+            SortKey::Pid => "PID",
+            SortKey::User => "USER",
+            SortKey::Cpu => "CPU",
+            SortKey::Ram => "RAM",
+            SortKey::Threads => "THR",
+            SortKey::Nice => "NI",
+            SortKey::IoRead => "READ",
+            SortKey::IoWrite => "WRITE",
+            SortKey::Fds => "FDS",
+            SortKey::Connections => "CONN",
+            SortKey::Uptime => "UPTIME",
+            SortKey::Command => "COMMAND",
+        }
+    }
+
+    /// The column to the right, wrapping around.
+    pub fn next(&self) -> SortKey {
+        let i = SortKey::ALL.iter().position(|key| key == self).unwrap_or(0);
+        SortKey::ALL[(i + 1) % SortKey::ALL.len()]
+    }
+
+    /// The column to the left, wrapping around.
+    pub fn previous(&self) -> SortKey {
+        let i = SortKey::ALL.iter().position(|key| key == self).unwrap_or(0);
+        SortKey::ALL[(i + SortKey::ALL.len() - 1) % SortKey::ALL.len()]
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<SortKey, String> {
+        SortKey::ALL
+            .iter()
+            .find(|key| key.label().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| format!("Unknown sort column {}", s))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    pub fn reversed(&self) -> SortOrder {
+        match self {
+            SortOrder::Ascending => SortOrder::Descending,
+            SortOrder::Descending => SortOrder::Ascending,
+        }
+    }
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<SortOrder, String> {
+        match s.to_lowercase().as_str() {
+            "asc" | "ascending" => Ok(SortOrder::Ascending),
+            "desc" | "descending" => Ok(SortOrder::Descending),
+            _ => Err(format!("Unknown sort order {}", s)),
+        }
+    }
+}
+
+/// One process read from /proc/[pid].
+#[derive(Debug, PartialEq)]
 pub struct Process {
     pid: u32,
+    ppid: u32,
+    threads: u32,
+    nice: i32,
+    cpus_allowed: String,
+    cgroup: String,
+    container_name: Option<String>,
     command: String,
     ram: u32,
     uid: String,
     user: String,
-    acive_jiffies: u32,
-    total_jiffies: u64,
+    acive_jiffies: u64,
+    start_time: u64,
+    sampled_at: f64,
     uptime: u64,
+    cpu_utilization: f64,
+    io: Option<IoStats>,
+    read_rate: Option<f64>,
+    write_rate: Option<f64>,
+    fds: Option<usize>,
+    socket_inodes: Vec<u64>,
+    fd_limit: Option<u64>,
+    connections: Option<usize>,
+}
+
+// bytes per second between two samples of a counter
+fn byte_rate(bytes: u64, seconds: f64) -> f64 {
+    if seconds <= 0.0 {
+        return 0.0;
+    }
+    bytes as f64 / seconds
+}
+
+// bytes that reached the storage, without the ones cancelled by truncation
+fn written_bytes(io: &IoStats) -> u64 {
+    io.write_bytes.saturating_sub(io.cancelled_write_bytes)
 }
 
 impl Process {
 
-    fn format_pid_path(pid: u32, file_name: &str) -> String {
-        format!("{}{}/{}", linux_parser::PROC_DIR, pid,file_name)
-    }
-
-    fn get_ram(pid: u32) -> u32 {
-        let file_path = Process::format_pid_path(pid, linux_parser::STATUS_FILENAME);
-        let status = file_utils::read_as_hashmap(&file_path, ":");
-        let status = status.unwrap();
-        let ram = status.get(RAM_LABEL_FILTER);
-        let ram = ram.unwrap();
-        let ram = ram.split_whitespace().next();
-        let ram = ram.unwrap();
-        let ram: u32 = match ram.parse() {
-                Ok(num) => num,
-                Err(_) => panic!("Failed convert {} to u32", ram),
+    fn format_pid_path(root: &FsRoot, pid: u32, file_name: &str) -> String {
+        root.pid_path(pid, file_name)
+    }
+
+    fn get_status(root: &FsRoot, pid: u32) -> Result<HashMap<String, String>, ParserError> {
+        let file_path = Process::format_pid_path(root, pid, linux_parser::STATUS_FILENAME);
+        file_utils::read_as_hashmap(&file_path, ":").map_err(|e| ParserError::io(&file_path, e))
+    }
+
+    fn get_ram(root: &FsRoot, pid: u32, status: &HashMap<String, String>) -> Result<u32, ParserError> {
+        // kernel threads have no user space memory and no VmData
+        let ram = match status.get(RAM_LABEL_FILTER) {
+            Some(ram) => ram,
+            None => return Ok(0),
         };
-        ram
+        let ram = ram.split_whitespace().next().unwrap_or("");
+        let file_path = Process::format_pid_path(root, pid, linux_parser::STATUS_FILENAME);
+        linux_parser::parse_value(&file_path, ram)
     }
 
-    fn get_uid(pid: u32) -> String {
-        let file_path = Process::format_pid_path(pid, linux_parser::STATUS_FILENAME);
-        let status = file_utils::read_as_hashmap(&file_path, ":");
-        let status = status.unwrap();
-        let uid = status.get(UID_LABEL_FILTER);
-        let uid = uid.unwrap();
-        let uid:Vec<&str> = uid.split_whitespace().collect();
-        let uid = uid.get(0);
-        let uid = uid.unwrap();
-        uid.to_string()
+    fn get_uid(root: &FsRoot, pid: u32, status: &HashMap<String, String>) -> Result<String, ParserError> {
+        let file_path = Process::format_pid_path(root, pid, linux_parser::STATUS_FILENAME);
+        let uid = status
+            .get(UID_LABEL_FILTER)
+            .and_then(|uid| uid.split_whitespace().next())
+            .ok_or_else(|| ParserError::missing(&file_path, UID_LABEL_FILTER))?;
+        Ok(uid.to_string())
+    }
 
+    // older kernels don't have the list, it's left empty then
+    pub(crate) fn get_cpus_allowed(status: &HashMap<String, String>) -> String {
+        status
+            .get(CPUS_ALLOWED_LABEL_FILTER)
+            .map(|cpus| cpus.trim().to_string())
+            .unwrap_or_default()
     }
 
-    fn get_command(pid: u32) -> String {
-        let file_path = Process::format_pid_path(pid, linux_parser::CMDLINE_FILENAME);
-        let cmd = file_utils::read_file_to_string(&file_path).unwrap();
+    fn get_command(root: &FsRoot, pid: u32) -> Result<String, ParserError> {
+        let file_path = Process::format_pid_path(root, pid, linux_parser::CMDLINE_FILENAME);
+        let cmd = linux_parser::read_to_string(&file_path)?;
         // remove the trailing \u{0}
         let mut chars = cmd.chars();
         chars.next_back();
         let cmd: String = chars.collect();
-        cmd
+        Ok(cmd)
     }
 
     // https://stackoverflow.com/questions/16726779/how-do-i-get-the-total-cpu-usage-of-an-application-from-proc-pid-stat
-    fn get_active_jiffies(pid: u32) -> u32 {
-        let file_path = Process::format_pid_path(pid, linux_parser::STAT_FILENAME);       
-        let stat = file_utils::read_file_to_string(&file_path).unwrap();
-        let stat: Vec<&str> = stat.trim().split_whitespace().collect();
-
-        let mut jiffies: u32 = 0;
-        for i in 13..17 {
-            let jiffie_cnt = stat[i].parse::<u32>().unwrap();
-            jiffies += jiffie_cnt;
-        }
-        jiffies
+    // like top, only the time spent by the process itself (utime + stime) is counted
+    pub(crate) fn get_active_jiffies(stat: &PidStat) -> u64 {
+        stat.utime + stat.stime
     }
 
-    fn get_user(pid_uid: &str) -> String {
-        let lines = file_utils::iter_lines(linux_parser::PASSWD_PATH);
-        let lines = match lines {
-            Ok(l) => l,
-            Err(_) => panic!("Failed to read file {}", linux_parser::PASSWD_PATH),
-        };
+    pub(crate) fn get_user(root: &FsRoot, pid_uid: &str) -> Result<String, ParserError> {
+        let passwd_path = root.etc_path(linux_parser::PASSWD_FILENAME);
+        let lines = file_utils::iter_lines(&passwd_path).map_err(|e| ParserError::io(&passwd_path, e))?;
         for line in lines {
-            let line = line.unwrap();
-            let line: Vec<&str> = line.trim().split(":").collect();
-            let user = line.get(0).unwrap();
-            let uid = line.get(2).unwrap();
-            if pid_uid == *uid {
-                return user.to_string();
+            let line = line.map_err(|e| ParserError::io(&passwd_path, e))?;
+            let line: Vec<&str> = line.trim().split(':').collect();
+            if let (Some(user), Some(uid)) = (line.first(), line.get(2)) {
+                if pid_uid == *uid {
+                    return Ok(user.to_string());
+                }
             }
         }
-        String::from("")
+        Ok(String::from(""))
     }
 
-    fn get_uptime(pid: u32, system_uptime: f64) -> u64 {
-        let file_path = Process::format_pid_path(pid, linux_parser::STAT_FILENAME);
-        let stat = file_utils::read_file_to_string(&file_path).unwrap();
-        let stat: Vec<&str> = stat.trim().split_whitespace().collect();
+    /// Reads the process `pid`, `system_uptime` being the seconds since boot.
+    /// Until [`Process::update_cpu_utilization`] is called, the CPU% is the lifetime average.
+    pub fn new(root: &FsRoot, pid: u32, system_uptime: f64) -> Result<Process, ParserError> {
+        let stat = linux_parser::get_pid_stat(root, pid)?;
+        let status = Process::get_status(root, pid)?;
+        let ram = Process::get_ram(root, pid, &status)?;
+        let command = Process::get_command(root, pid)?;
+        let uid = Process::get_uid(root, pid, &status)?;
+        let user = Process::get_user(root, &uid)?;
+        let acive_jiffies = Process::get_active_jiffies(&stat);
+        let start_time = stat.start_time;
+        let ppid = stat.ppid;
+        let threads = stat.num_threads;
+        let nice = stat.nice;
+        let cpus_allowed = Process::get_cpus_allowed(&status);
+        // the io file of other users' processes can't be read without privileges,
+        // the process is still shown, without I/O statistics
+        let io = linux_parser::get_io_stats(root, pid).ok();
+        // kernels without cgroups have no cgroup file, show the process anyway
+        let cgroup = linux_parser::get_cgroup(root, pid).unwrap_or_default();
+        // like the io file, the fd directory of other users' processes needs privileges.
+        // the fds are read once per refresh, for the count and for the sockets
+        let open_fds = fd::read_fds(root, pid).ok();
+        let fds = open_fds.as_ref().map(|fds| fds.len());
+        let socket_inodes: Vec<u64> = open_fds
+            .iter()
+            .flatten()
+            .filter_map(|fd| fd::socket_inode(&fd.target))
+            .collect();
+        let fd_limit = linux_parser::get_open_files_limit(root, pid).ok().flatten();
 
-        let start_time = stat.get(21).unwrap();
         let clk_per_sec = linux_parser::get_sc_clk_tck() as f64;
-        let start_time = start_time.parse::<u32>().unwrap() as f64 / clk_per_sec;
-        (system_uptime - start_time) as u64
-    }
-
-    pub fn new(pid: u32, total_jiffies: u64, system_uptime: f64) -> Process {
-        let ram = Process::get_ram(pid);
-        let command = Process::get_command(pid);
-        let uid = Process::get_uid(pid);
-        let user = Process::get_user(&uid);
-        let acive_jiffies = Process::get_active_jiffies(pid);
-        let uptime = Process::get_uptime(pid, system_uptime);
-        // get uid
-        // get user
-        // get uptime
-        Process { 
+        let seconds = (system_uptime - start_time as f64 / clk_per_sec).max(0.0);
+        // without a previous sample, the best we can do is the lifetime average
+        let cpu_utilization = cpu_percent(acive_jiffies, seconds, clk_per_sec);
+        let read_rate = io.map(|io| byte_rate(io.read_bytes, seconds));
+        let write_rate = io.map(|io| byte_rate(written_bytes(&io), seconds));
+
+        Ok(Process { 
             pid, 
+            ppid,
+            threads,
+            nice,
+            cpus_allowed,
+            cgroup,
+            container_name: None,
             command,
             ram, 
             uid, 
             user,
             acive_jiffies,
-            total_jiffies,
-            uptime,
+            start_time,
+            sampled_at: system_uptime,
+            uptime: seconds as u64,
+            cpu_utilization,
+            io,
+            read_rate,
+            write_rate,
+            fds,
+            socket_inodes,
+            fd_limit,
+            connections: None,
+        })
+    }
+
+    /// A pid can be reused, so processes are matched across refreshes by pid and start time.
+    pub fn key(&self) -> (u32, u64) { (self.pid, self.start_time) }
+
+    /// Sets the CPU% to the usage over the interval since `previous` was sampled.
+    pub fn update_cpu_utilization(&mut self, previous: &Process) {
+        if previous.key() != self.key() {
+            return;
+        }
+        let clk_per_sec = linux_parser::get_sc_clk_tck() as f64;
+        let jiffies = self.acive_jiffies.saturating_sub(previous.acive_jiffies);
+        let seconds = self.sampled_at - previous.sampled_at;
+        self.cpu_utilization = cpu_percent(jiffies, seconds, clk_per_sec);
+    }
+
+    /// Sets the read and write rates to the ones over the interval since `previous` was sampled.
+    pub fn update_io_rates(&mut self, previous: &Process) {
+        if previous.key() != self.key() {
+            return;
+        }
+        if let (Some(io), Some(prev)) = (self.io, previous.io) {
+            let seconds = self.sampled_at - previous.sampled_at;
+            self.read_rate = Some(byte_rate(io.read_bytes.saturating_sub(prev.read_bytes), seconds));
+            self.write_rate = Some(byte_rate(written_bytes(&io).saturating_sub(written_bytes(&prev)), seconds));
         }
     }
 
     pub fn pid(&self) -> u32 { self.pid }
 
+    /// Pid of the parent process, 0 for init and kthreadd.
+    pub fn ppid(&self) -> u32 { self.ppid }
+
+    /// Number of threads, including the main thread.
+    pub fn threads(&self) -> u32 { self.threads }
+
+    /// From 19 (lowest priority) to -20 (highest priority).
+    pub fn nice(&self) -> i32 { self.nice }
+
+    /// The CPUs the process may run on, e.g. `0-3,8`.
+    pub fn cpus_allowed(&self) -> &str { &self.cpus_allowed }
+
+    /// The cgroup v2 path, e.g. `/system.slice/nginx.service`, empty without cgroup v2.
+    pub fn cgroup(&self) -> &str { &self.cgroup }
+
+    /// The full ID of the docker, containerd or podman container the process runs in.
+    pub fn container_id(&self) -> Option<&str> { container::container_id(&self.cgroup) }
+
+    /// The name of the container, once a [`ContainerResolver`] knows it.
+    pub fn container_name(&self) -> Option<&str> { self.container_name.as_deref() }
+
+    pub(crate) fn resolve_container(&mut self, resolver: &dyn ContainerResolver) {
+        self.container_name = self.container_id().and_then(|id| resolver.name(id));
+    }
+
+    /// Size of the data segment in kB.
     pub fn ram(&self) -> u32 { self.ram }
 
     pub fn user(&self) -> String { self.user.clone() }
 
-    pub fn uid(&self) -> String { self.user.clone() }
+    pub fn uid(&self) -> String { self.uid.clone() }
 
-    pub fn cpu_utilization(&self) -> f64 {      
-        let system_uptime = linux_parser::get_uptime();
+    /// Seconds since the process started.
+    pub fn uptime(&self) -> u64 { self.uptime }
 
-        let file_path = Process::format_pid_path(self.pid, linux_parser::STAT_FILENAME);
-        let stat = file_utils::read_file_to_string(&file_path).unwrap();
-        let stat: Vec<&str> = stat.trim().split_whitespace().collect();
+    /// CPU% of one core, so a process can go above 100 on a multi core system.
+    pub fn cpu_utilization(&self) -> f64 { self.cpu_utilization }
 
-        let start_time = stat.get(21).unwrap();
-        let clk_per_sec = linux_parser::get_sc_clk_tck() as f64;
-        let start_time = start_time.parse::<u32>().unwrap() as f64 / clk_per_sec;
-        let seconds = system_uptime - start_time;
+    /// The counters of /proc/[pid]/io, `None` if it can't be read.
+    pub fn io(&self) -> Option<&IoStats> { self.io.as_ref() }
+
+    /// Bytes per second read from the storage, `None` if the io file can't be read.
+    pub fn read_rate(&self) -> Option<f64> { self.read_rate }
+
+    /// Bytes per second written to the storage, minus cancelled writes.
+    pub fn write_rate(&self) -> Option<f64> { self.write_rate }
+
+    /// Number of open file descriptors, `None` if /proc/[pid]/fd can't be read.
+    pub fn fds(&self) -> Option<usize> { self.fds }
+
+    /// The soft limit on open files, `None` if unlimited or unreadable.
+    pub fn fd_limit(&self) -> Option<u64> { self.fd_limit }
+
+    /// Number of TCP and UDP connections, `None` until the sockets are read
+    /// or if the fds of the process can't be read.
+    pub fn connections(&self) -> Option<usize> { self.connections }
 
-        let total_time = self.acive_jiffies as f64 / clk_per_sec;
+    // the inodes of the sockets among the open fds, empty if they can't be read
+    pub(crate) fn socket_inodes(&self) -> &[u64] { &self.socket_inodes }
 
-        100.0 * total_time / seconds
+    pub(crate) fn set_connections(&mut self, connections: &HashMap<u32, usize>) {
+        self.connections = self.fds.map(|_| connections.get(&self.pid).copied().unwrap_or(0));
     }
 
-    pub fn command(&self) -> String {
-        if self.command.len() > 40 {
+    /// True if the process uses up most of its open files limit.
+    pub fn is_near_fd_limit(&self) -> bool {
+        self.fds.is_some_and(|fds| fd::is_near_limit(fds, self.fd_limit))
+    }
+
+    /// The full command line, arguments separated by `\0`.
+    pub fn command(&self) -> &str { &self.command }
+
+    /// Orders processes by `key`, lowest first.
+    pub fn compare(&self, other: &Process, key: SortKey) -> Ordering {
+        match key {
+            SortKey::Pid => self.pid.cmp(&other.pid),
+            SortKey::User => self.user.cmp(&other.user),
+            SortKey::Cpu => self.cpu_utilization.partial_cmp(&other.cpu_utilization).unwrap_or(Ordering::Equal),
+            SortKey::Ram => self.ram.cmp(&other.ram),
+            SortKey::Threads => self.threads.cmp(&other.threads),
+            SortKey::Nice => self.nice.cmp(&other.nice),
+            // unreadable rates sort below every readable one
+            SortKey::IoRead => self.read_rate.partial_cmp(&other.read_rate).unwrap_or(Ordering::Equal),
+            SortKey::IoWrite => self.write_rate.partial_cmp(&other.write_rate).unwrap_or(Ordering::Equal),
+            SortKey::Fds => self.fds.cmp(&other.fds),
+            SortKey::Connections => self.connections.cmp(&other.connections),
+            SortKey::Uptime => self.uptime.cmp(&other.uptime),
+            SortKey::Command => self.command.cmp(&other.command),
+        }
+    }
+
+    // the last part of the cgroup path names the service, scope or container
+    pub(crate) fn short_cgroup(&self) -> &str {
+        match self.cgroup.as_str() {
+            "" => "-",
+            "/" => "/",
+            cgroup => cgroup.rsplit('/').next().unwrap_or(cgroup),
+        }
+    }
+
+    // the open files and their limit, e.g. 12/1024
+    pub(crate) fn format_fds(&self) -> String {
+        match (self.fds, self.fd_limit) {
+            (Some(fds), Some(limit)) => format!("{}/{}", fds, limit),
+            (Some(fds), None) => fds.to_string(),
+            (None, _) => String::from("-"),
+        }
+    }
+
+    // the container name, or the short ID if there is no name
+    pub(crate) fn short_container(&self) -> &str {
+        match (&self.container_name, self.container_id()) {
+            (Some(name), _) => name,
+            (None, Some(id)) => &id[..container::SHORT_ID_LEN],
+            (None, None) => "-",
+        }
+    }
+
+    pub(crate) fn short_command(&self) -> String {
+        // arguments are separated by \0 in /proc/[pid]/cmdline
+        let command = self.command.replace('\0', " ");
+        if command.chars().count() > 40 {
             // cut down long commands to 40 characters
-            let trunc_cmd: String = self.command.chars().take(37).collect();
+            let trunc_cmd: String = command.chars().take(37).collect();
             return format!("{}...", trunc_cmd)
         }
-        self.command.clone()
+        command
     }
 
     pub fn head_str() -> String {
         format!(
-            "{:<8} {:<8} {:<8} {:<10} {:>10} {:<40}",
+            "{:<8} {:<8} {:<8} {:<10} {:>5} {:>3} {:<8} {:>8} {:>8} {:>11} {:>5} {:<12} {:<20} {:>10} {:<40}",
             "PID",
             "USER",
             "CPU[%]",
             "RAM[KB]",
+            "THR",
+            "NI",
+            "CPUS",
+            "READ/s",
+            "WRITE/s",
+            "FDS",
+            "CONN",
+            "CONTAINER",
+            "CGROUP",
             "UPTIME",
             "COMMAND",
         )
     }
 }
 
+pub(crate) fn cpu_percent(jiffies: u64, seconds: f64, clk_per_sec: f64) -> f64 {
+    // don't divide by zero
+    if seconds <= 0.0 {
+        return 0.0;
+    }
+    100.0 * (jiffies as f64 / clk_per_sec) / seconds
+}
+
+// a placeholder for processes whose io file can't be read
+pub(crate) fn format_rate(rate: Option<f64>) -> String {
+    match rate {
+        Some(rate) => format::format_bytes(rate),
+        None => String::from("-"),
+    }
+}
+
 impl fmt::Display for Process {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
-            f, "{:<8} {:<8} {:<8.2} {:<10} {:>10} {:<40}",
+            f, "{:<8} {:<8} {:<8.2} {:<10} {:>5} {:>3} {:<8.8} {:>8} {:>8} {:>11} {:>5} {:<12.12} {:<20.20} {:>10} {:<40}",
             self.pid(), 
             self.user(), 
             self.cpu_utilization(), 
             self.ram(), 
+            self.threads(),
+            self.nice(),
+            self.cpus_allowed(),
+            format_rate(self.read_rate),
+            format_rate(self.write_rate),
+            self.format_fds(),
+            self.connections.map_or_else(|| String::from("-"), |count| count.to_string()),
+            self.short_container(),
+            self.short_cgroup(),
             format::format_seconds(self.uptime), 
-            self.command()
+            self.short_command()
         )
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn interval_cpu_utilization() {
+        let root = linux_parser::tests::fixture_root();
+        let clk_per_sec = linux_parser::get_sc_clk_tck() as u64;
+        let previous = Process {
+            acive_jiffies: 1000 - 2 * clk_per_sec,
+            ..Process::new(&root, 42, 998.5).unwrap()
+        };
+        let mut process = Process::new(&root, 42, 1000.5).unwrap();
+        process.update_cpu_utilization(&previous);
+        // two seconds of CPU time over a two second interval
+        assert_eq!(process.cpu_utilization(), 100.0);
+    }
+
+    #[test]
+    fn reused_pid_keeps_lifetime_utilization() {
+        let root = linux_parser::tests::fixture_root();
+        let previous = Process {
+            start_time: 10,
+            ..Process::new(&root, 42, 998.5).unwrap()
+        };
+        let mut process = Process::new(&root, 42, 1000.5).unwrap();
+        let lifetime = process.cpu_utilization();
+        process.update_cpu_utilization(&previous);
+        assert_eq!(process.cpu_utilization(), lifetime);
+    }
+
+    #[test]
+    fn interval_io_rates() {
+        let root = linux_parser::tests::fixture_root();
+        let mut process = Process::new(&root, 42, 1000.5).unwrap();
+        let io = *process.io().unwrap();
+        let previous = Process {
+            io: Some(IoStats { read_bytes: io.read_bytes - 4096, write_bytes: io.write_bytes - 2048, ..io }),
+            ..Process::new(&root, 42, 998.5).unwrap()
+        };
+        process.update_io_rates(&previous);
+        assert_eq!(process.read_rate(), Some(2048.0));
+        assert_eq!(process.write_rate(), Some(1024.0));
+    }
+
+    #[test]
+    fn unreadable_io_is_a_placeholder() {
+        let root = linux_parser::tests::fixture_root();
+        let process = Process::new(&root, 1, 1000.5).unwrap();
+        assert!(process.io().is_none());
+        assert_eq!(process.read_rate(), None);
+        assert_eq!(process.write_rate(), None);
+        assert_eq!(format_rate(None), "-");
+        // the rates are right aligned under their headers
+        let head = Process::head_str();
+        let row = process.to_string();
+        let cell = |label: &str| {
+            let end = head.find(label).unwrap() + label.len();
+            row[end - 8..end].to_string()
+        };
+        assert_eq!(cell("READ/s"), "       -");
+        assert_eq!(cell("WRITE/s"), "       -");
+    }
+
+    #[test]
+    fn parse_sort_key() {
+        assert_eq!("ram".parse::<SortKey>().unwrap(), SortKey::Ram);
+        assert_eq!("Command".parse::<SortKey>().unwrap(), SortKey::Command);
+        assert!("size".parse::<SortKey>().is_err());
+        assert_eq!("asc".parse::<SortOrder>().unwrap(), SortOrder::Ascending);
+    }
+
+    #[test]
+    fn kernel_thread_without_vm_data() {
+        let root = linux_parser::tests::fixture_root();
+        let process = Process::new(&root, 2, 1000.5).unwrap();
+        assert_eq!(process.ram(), 0);
+        assert_eq!(process.user(), "root");
+        assert_eq!(process.threads(), 1);
+        assert_eq!(process.nice(), 0);
+        assert_eq!(process.cpus_allowed(), "0-1");
+        assert_eq!(process.short_cgroup(), "/");
+    }
+
+    #[test]
+    fn container_of_docker_scope() {
+        let root = linux_parser::tests::fixture_root();
+        let mut process = Process::new(&root, 42, 1000.5).unwrap();
+        assert!(process.container_id().unwrap().starts_with("3f4e1a2b5c6d"));
+        assert_eq!(process.short_container(), "3f4e1a2b5c6d");
+        process.resolve_container(&crate::container::ContainerNames::from_json(r#"{"3f4e1a2b5c6d": "worker"}"#).unwrap());
+        assert_eq!(process.container_name(), Some("worker"));
+        assert_eq!(process.short_container(), "worker");
+
+        let init = Process::new(&root, 1, 1000.5).unwrap();
+        assert_eq!(init.container_id(), None);
+        assert_eq!(init.short_container(), "-");
+    }
+
+    #[test]
+    fn open_files_near_limit() {
+        let root = linux_parser::tests::fixture_root();
+        let worker = Process::new(&root, 42, 1000.5).unwrap();
+        assert_eq!((worker.fds(), worker.fd_limit()), (Some(6), Some(6)));
+        assert!(worker.is_near_fd_limit());
+        assert_eq!(worker.format_fds(), "6/6");
+        // no fd directory to read
+        let init = Process::new(&root, 1, 1000.5).unwrap();
+        assert_eq!(init.fds(), None);
+        assert!(!init.is_near_fd_limit());
+        assert_eq!(init.format_fds(), "-");
+    }
+
+    #[test]
+    fn vanished_process() {
+        let root = linux_parser::tests::fixture_root();
+        let err = Process::new(&root, 7, 1000.5).unwrap_err();
+        assert!(err.is_vanished());
+    }
+}
@@ -0,0 +1,258 @@
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+
+use crate::cpu::CpuBreakdown;
+use crate::process::Process;
+use crate::system::System;
+
+/// The version of the JSON schema of [`Snapshot`]. Fields are only ever added within a version,
+/// it goes up when a field is renamed, removed or changes its meaning or unit.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A [`System`] snapshot in the shape of the versioned JSON schema, for scripts and other tools.
+/// Percentages go from 0 to 100 (per core for processes), memory is in kB, rates in bytes per second
+/// and durations in seconds. Values that can't be read, e.g. other users' I/O without root, are `null`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Snapshot {
+    pub schema_version: u32,
+    /// The time of the snapshot in RFC 3339, UTC.
+    pub timestamp: String,
+    pub system: SystemInfo,
+    pub cpu: CpuInfo,
+    pub memory: MemoryInfo,
+    /// The processes matching the filter, in the sort order.
+    pub processes: Vec<ProcessInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SystemInfo {
+    pub os: String,
+    pub kernel: String,
+    pub uptime_seconds: u64,
+    pub load_average: [f64; 3],
+    /// Threads that are runnable and that exist, from /proc/loadavg.
+    pub tasks_runnable: u32,
+    pub tasks_total: u32,
+    pub processes_total: u32,
+    pub processes_running: u32,
+    pub forks_per_second: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CpuInfo {
+    pub cores: u32,
+    pub utilization_percent: f64,
+    pub breakdown_percent: CpuBreakdownInfo,
+    pub core_utilization_percent: Vec<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CpuBreakdownInfo {
+    pub user: f64,
+    pub nice: f64,
+    pub system: f64,
+    pub idle: f64,
+    pub iowait: f64,
+    pub irq: f64,
+    pub softirq: f64,
+    pub steal: f64,
+    pub guest: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemoryInfo {
+    pub total_kb: u64,
+    pub used_kb: u64,
+    pub available_kb: u64,
+    pub free_kb: u64,
+    pub buffers_kb: u64,
+    pub cached_kb: u64,
+    pub shared_kb: u64,
+    pub utilization_percent: f64,
+    pub swap_total_kb: u64,
+    pub swap_used_kb: u64,
+    pub swap_utilization_percent: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    pub uid: String,
+    pub user: String,
+    /// The command line with the arguments joined by spaces, empty for kernel threads.
+    pub command: String,
+    pub args: Vec<String>,
+    pub cpu_percent: f64,
+    /// The data segment, like the RAM column.
+    pub ram_kb: u32,
+    pub threads: u32,
+    pub nice: i32,
+    pub cpus_allowed: String,
+    pub cgroup: String,
+    pub container_id: Option<String>,
+    pub container_name: Option<String>,
+    pub uptime_seconds: u64,
+    pub read_bytes_per_second: Option<f64>,
+    pub write_bytes_per_second: Option<f64>,
+    pub open_files: Option<usize>,
+    /// The soft limit on open files, `null` for unlimited or unreadable.
+    pub open_files_limit: Option<u64>,
+    pub connections: Option<usize>,
+}
+
+fn percent(fraction: f64) -> f64 {
+    fraction * 100.0
+}
+
+impl From<CpuBreakdown> for CpuBreakdownInfo {
+    fn from(breakdown: CpuBreakdown) -> CpuBreakdownInfo {
+        CpuBreakdownInfo {
+            user: percent(breakdown.user),
+            nice: percent(breakdown.nice),
+            system: percent(breakdown.system),
+            idle: percent(breakdown.idle),
+            iowait: percent(breakdown.iowait),
+            irq: percent(breakdown.irq),
+            softirq: percent(breakdown.softirq),
+            steal: percent(breakdown.steal),
+            guest: percent(breakdown.guest),
+        }
+    }
+}
+
+impl From<&Process> for ProcessInfo {
+    fn from(process: &Process) -> ProcessInfo {
+        ProcessInfo {
+            pid: process.pid(),
+            ppid: process.ppid(),
+            uid: process.uid(),
+            user: process.user(),
+            command: process.command().trim_end_matches('\0').replace('\0', " "),
+            args: process.command().split_terminator('\0').map(String::from).collect(),
+            cpu_percent: process.cpu_utilization(),
+            ram_kb: process.ram(),
+            threads: process.threads(),
+            nice: process.nice(),
+            cpus_allowed: process.cpus_allowed().to_string(),
+            cgroup: process.cgroup().to_string(),
+            container_id: process.container_id().map(String::from),
+            container_name: process.container_name().map(String::from),
+            uptime_seconds: process.uptime(),
+            read_bytes_per_second: process.read_rate(),
+            write_bytes_per_second: process.write_rate(),
+            open_files: process.fds(),
+            open_files_limit: process.fd_limit(),
+            connections: process.connections(),
+        }
+    }
+}
+
+impl Snapshot {
+    /// Takes the snapshot of `sys` as of its last refresh, with the first `process_limit`
+    /// processes, all of them for 0.
+    pub fn new(sys: &System, process_limit: usize) -> Snapshot {
+        let load_avg = sys.load_avg();
+        let cpu = sys.cpu();
+        let memory = sys.memory();
+        let stats = memory.stats();
+        let process_limit = if process_limit == 0 { usize::MAX } else { process_limit };
+        Snapshot {
+            schema_version: SCHEMA_VERSION,
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            system: SystemInfo {
+                os: sys.os().to_string(),
+                kernel: sys.kernel().to_string(),
+                uptime_seconds: sys.uptime() as u64,
+                load_average: [load_avg.one, load_avg.five, load_avg.fifteen],
+                tasks_runnable: load_avg.runnable,
+                tasks_total: load_avg.total,
+                processes_total: sys.total_processes(),
+                processes_running: sys.running_processes(),
+                forks_per_second: sys.forks_per_second(),
+            },
+            cpu: CpuInfo {
+                cores: cpu.get_cpu_count(),
+                utilization_percent: percent(cpu.utilization()),
+                breakdown_percent: cpu.breakdown().into(),
+                core_utilization_percent: cpu.core_utilization().into_iter().map(percent).collect(),
+            },
+            memory: MemoryInfo {
+                total_kb: stats.total,
+                used_kb: memory.used(),
+                available_kb: stats.available,
+                free_kb: stats.free,
+                buffers_kb: stats.buffers,
+                cached_kb: memory.cached(),
+                shared_kb: stats.shared,
+                utilization_percent: percent(memory.utilization()),
+                swap_total_kb: stats.swap_total,
+                swap_used_kb: memory.swap_used(),
+                swap_utilization_percent: percent(memory.swap_utilization()),
+            },
+            processes: sys
+                .filtered_processes()
+                .into_iter()
+                .take(process_limit)
+                .map(ProcessInfo::from)
+                .collect(),
+        }
+    }
+
+    /// The snapshot as one JSON document, indented for reading.
+    pub fn to_json_pretty(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// The snapshot as a single line of JSON, for newline-delimited streams.
+    pub fn to_json_line(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::linux_parser;
+    use serde_json::Value;
+
+    fn keys(value: &Value) -> Vec<&str> {
+        value.as_object().unwrap().keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn fixture_snapshot() {
+        let sys = System::new(linux_parser::tests::fixture_root()).unwrap();
+        let snapshot = Snapshot::new(&sys, 0);
+        assert_eq!(snapshot.schema_version, SCHEMA_VERSION);
+        assert_eq!(snapshot.processes.len(), sys.total_processes() as usize);
+        let worker = snapshot.processes.iter().find(|process| process.pid == 42).unwrap();
+        assert_eq!(worker.open_files, Some(6));
+        assert_eq!(worker.container_id.as_deref().map(|id| &id[..12]), Some("3f4e1a2b5c6d"));
+        assert_eq!(worker.args.first().map(String::as_str), worker.command.split(' ').next());
+        assert_eq!(Snapshot::new(&sys, 1).processes.len(), 1);
+    }
+
+    // tools parse these names, changing them needs a new SCHEMA_VERSION
+    #[test]
+    fn stable_field_names() {
+        let sys = System::new(linux_parser::tests::fixture_root()).unwrap();
+        let line = Snapshot::new(&sys, 1).to_json_line().unwrap();
+        assert!(!line.contains('\n'));
+        let json: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(keys(&json), vec!["cpu", "memory", "processes", "schema_version", "system", "timestamp"]);
+        assert_eq!(
+            keys(&json["processes"][0]),
+            vec![
+                "args", "cgroup", "command", "connections", "container_id", "container_name", "cpu_percent",
+                "cpus_allowed", "nice", "open_files", "open_files_limit", "pid", "ppid", "ram_kb",
+                "read_bytes_per_second", "threads", "uid", "uptime_seconds", "user", "write_bytes_per_second",
+            ]
+        );
+        assert_eq!(keys(&json["cpu"]), vec!["breakdown_percent", "core_utilization_percent", "cores", "utilization_percent"]);
+        assert_eq!(keys(&json["memory"]).len(), 11);
+        assert_eq!(keys(&json["system"]).len(), 9);
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+
+use crate::error::ParserError;
+use crate::linux_parser::{self, FsRoot, InetSocketStats, UnixSocketStats};
+use crate::process::Process;
+
+// TCP states, see include/net/tcp_states.h
+const TCP_ESTABLISHED: u8 = 0x01;
+const TCP_CLOSE: u8 = 0x07;
+const TCP_LISTEN: u8 = 0x0A;
+
+/// The socket table a socket was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Tcp,
+    Tcp6,
+    Udp,
+    Udp6,
+    Unix,
+}
+
+const INET_TABLES: [(Protocol, &str); 4] = [
+    (Protocol::Tcp, linux_parser::NET_TCP_FILENAME),
+    (Protocol::Tcp6, linux_parser::NET_TCP6_FILENAME),
+    (Protocol::Udp, linux_parser::NET_UDP_FILENAME),
+    (Protocol::Udp6, linux_parser::NET_UDP6_FILENAME),
+];
+
+impl Protocol {
+    pub fn label(&self) -> &str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Tcp6 => "tcp6",
+            Protocol::Udp => "udp",
+            Protocol::Udp6 => "udp6",
+            Protocol::Unix => "unix",
+        }
+    }
+
+    fn is_udp(&self) -> bool {
+        matches!(self, Protocol::Udp | Protocol::Udp6)
+    }
+}
+
+// the names ss uses for the TCP states
+fn tcp_state_label(state: u8) -> &'static str {
+    match state {
+        0x01 => "ESTAB",
+        0x02 => "SYN-SENT",
+        0x03 => "SYN-RECV",
+        0x04 => "FIN-WAIT-1",
+        0x05 => "FIN-WAIT-2",
+        0x06 => "TIME-WAIT",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE-WAIT",
+        0x09 => "LAST-ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+// * for the unspecified address and port, like ss
+fn format_addr(addr: &SocketAddr) -> String {
+    match (addr.ip().is_unspecified(), addr.port()) {
+        (true, 0) => String::from("*"),
+        (true, port) => format!("*:{}", port),
+        _ => addr.to_string(),
+    }
+}
+
+// the kernel's default ip_local_port_range
+const DEFAULT_LOCAL_PORT_RANGE: (u16, u16) = (32768, 60999);
+
+// UDP has no listen state, so this guesses which unconnected sockets are servers. The
+// tables don't say whether a socket was bound with bind(2) or got a port on its first
+// sendto(2). A port picked by the kernel comes from the ephemeral range and goes with
+// the wildcard address, so a socket counts as listening if it has no remote address
+// and either a specific local address or a wildcard address with a port outside that range.
+// A server bound to a port inside the range, e.g. with port 0, is missed.
+fn is_udp_listener(stats: &InetSocketStats, (first, last): (u16, u16)) -> bool {
+    let port = stats.local.port();
+    if port == 0 || !stats.remote.ip().is_unspecified() || stats.remote.port() != 0 {
+        return false;
+    }
+    !stats.local.ip().is_unspecified() || !(first..=last).contains(&port)
+}
+
+/// One socket of the /proc/net tables, with the processes that have it open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Socket {
+    pub protocol: Protocol,
+    /// The local address and port, or the path of a unix socket.
+    pub local: String,
+    pub remote: String,
+    /// The local port, `None` for unix sockets.
+    pub port: Option<u16>,
+    /// The state as ss names it, e.g. LISTEN, ESTAB or UNCONN.
+    pub state: &'static str,
+    /// Listening for connections, or a UDP socket that looks bound by a server, see `is_udp_listener`.
+    pub listening: bool,
+    /// A TCP connection, or a UDP socket connected to a peer.
+    pub connected: bool,
+    pub inode: u64,
+    /// The pids with the socket open, empty if their fds can't be read.
+    pub pids: Vec<u32>,
+}
+
+impl Socket {
+    fn inet(protocol: Protocol, stats: &InetSocketStats, ephemeral_ports: (u16, u16)) -> Socket {
+        let (state, listening, connected) = if protocol.is_udp() {
+            match stats.state {
+                TCP_ESTABLISHED => ("ESTAB", false, true),
+                _ => ("UNCONN", is_udp_listener(stats, ephemeral_ports), false),
+            }
+        } else {
+            let state = stats.state;
+            (tcp_state_label(state), state == TCP_LISTEN, state != TCP_LISTEN && state != TCP_CLOSE)
+        };
+        Socket {
+            protocol,
+            local: format_addr(&stats.local),
+            remote: format_addr(&stats.remote),
+            port: Some(stats.local.port()),
+            state,
+            listening,
+            connected,
+            inode: stats.inode,
+            pids: vec![],
+        }
+    }
+
+    fn unix(stats: &UnixSocketStats) -> Socket {
+        let state = match (stats.listening, stats.connected) {
+            (true, _) => "LISTEN",
+            (false, true) => "ESTAB",
+            (false, false) => "UNCONN",
+        };
+        Socket {
+            protocol: Protocol::Unix,
+            local: if stats.path.is_empty() { String::from("*") } else { stats.path.clone() },
+            remote: String::from("*"),
+            port: None,
+            state,
+            listening: stats.listening,
+            connected: stats.connected,
+            inode: stats.inode,
+            pids: vec![],
+        }
+    }
+
+    /// A TCP or UDP socket connected to a peer, counted as a connection of its processes.
+    pub fn is_connection(&self) -> bool {
+        self.connected && self.protocol != Protocol::Unix
+    }
+}
+
+// the pids of `processes` that have each socket inode open, from the fds read with the processes
+fn socket_owners(processes: &[Process]) -> HashMap<u64, Vec<u32>> {
+    let mut owners: HashMap<u64, Vec<u32>> = HashMap::new();
+    for process in processes {
+        for inode in process.socket_inodes() {
+            let socket_pids = owners.entry(*inode).or_default();
+            // a forked child shares the sockets of its parent, and a process can
+            // have the same socket open twice
+            if !socket_pids.contains(&process.pid()) {
+                socket_pids.push(process.pid());
+            }
+        }
+    }
+    owners
+}
+
+/// Reads every TCP, UDP and unix socket and finds the `processes` that have them open.
+/// The IPv6 tables are skipped on kernels without IPv6.
+pub fn read_sockets(root: &FsRoot, processes: &[Process]) -> Result<Vec<Socket>, ParserError> {
+    let ephemeral_ports = linux_parser::get_local_port_range(root).unwrap_or(DEFAULT_LOCAL_PORT_RANGE);
+    let mut sockets: Vec<Socket> = Vec::new();
+    for (protocol, file_name) in INET_TABLES.iter() {
+        match linux_parser::get_inet_sockets(root, file_name) {
+            Ok(stats) => sockets.extend(stats.iter().map(|stats| Socket::inet(*protocol, stats, ephemeral_ports))),
+            Err(ParserError::Io { .. }) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    match linux_parser::get_unix_sockets(root) {
+        Ok(stats) => sockets.extend(stats.iter().map(Socket::unix)),
+        Err(ParserError::Io { .. }) => {}
+        Err(e) => return Err(e),
+    }
+
+    let owners = socket_owners(processes);
+    for socket in sockets.iter_mut() {
+        // sockets in TIME-WAIT no longer belong to a process and have inode 0
+        if let Some(pids) = owners.get(&socket.inode) {
+            socket.pids = pids.clone();
+        }
+    }
+    Ok(sockets)
+}
+
+/// Counts the TCP and UDP connections of every pid.
+pub fn count_connections(sockets: &[Socket]) -> HashMap<u32, usize> {
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    for socket in sockets.iter().filter(|socket| socket.is_connection()) {
+        for pid in &socket.pids {
+            *counts.entry(*pid).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// One line of the listening sockets view: a socket and the process it belongs to.
+#[derive(Debug)]
+pub struct SocketRow<'a> {
+    pub socket: &'a Socket,
+    /// The first process with the socket open, `None` if it isn't known.
+    pub process: Option<&'a Process>,
+}
+
+impl SocketRow<'_> {
+    pub fn head_str() -> String {
+        format!(
+            "{:<5} {:<10} {:<40} {:<12} {:<8} {:<40}",
+            "PROTO",
+            "STATE",
+            "LOCAL",
+            "PID",
+            "USER",
+            "COMMAND",
+        )
+    }
+}
+
+impl fmt::Display for SocketRow<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (pid, user, command) = match self.process {
+            Some(process) => {
+                // e.g. 42 (+3) for a socket shared by forked workers
+                let pid = match self.socket.pids.len() {
+                    0 | 1 => process.pid().to_string(),
+                    n => format!("{} (+{})", process.pid(), n - 1),
+                };
+                (pid, process.user(), process.short_command())
+            }
+            None => (String::from("-"), String::from("-"), String::from("-")),
+        };
+        write!(
+            f, "{:<5} {:<10} {:<40} {:<12} {:<8} {:<40}",
+            self.socket.protocol.label(),
+            self.socket.state,
+            self.socket.local,
+            pid,
+            user,
+            command,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn fixture_sockets() {
+        let sockets = read_sockets(&linux_parser::tests::fixture_root(), &linux_parser::tests::fixture_processes()).unwrap();
+        let listening: Vec<(&str, &str)> = sockets
+            .iter()
+            .filter(|socket| socket.listening)
+            .map(|socket| (socket.protocol.label(), socket.local.as_str()))
+            .collect();
+        assert_eq!(
+            listening,
+            vec![
+                ("tcp", "*:8080"),
+                ("tcp6", "*:22"),
+                ("tcp6", "[::1]:8081"),
+                ("udp", "127.0.0.53:53"),
+                ("udp", "*:68"),
+                ("unix", "/run/systemd/private"),
+                ("unix", "@/tmp/.X11-unix/X0"),
+            ]
+        );
+        let http = &sockets[0];
+        assert_eq!((http.state, http.port, http.pids.clone()), ("LISTEN", Some(8080), vec![42]));
+        // the fixture's sshd has no fd directory
+        assert!(sockets[2].pids.is_empty());
+        // a client socket that got an ephemeral port when it first sent
+        let client = sockets.iter().find(|socket| socket.inode == 3102).unwrap();
+        assert_eq!((client.state, client.listening), ("UNCONN", false));
+    }
+
+    #[test]
+    fn udp_listeners() {
+        let udp = |local: &str, remote: &str| InetSocketStats {
+            local: local.parse().unwrap(),
+            remote: remote.parse().unwrap(),
+            state: TCP_CLOSE,
+            uid: 0,
+            inode: 1,
+        };
+        let range = DEFAULT_LOCAL_PORT_RANGE;
+        assert!(is_udp_listener(&udp("0.0.0.0:53", "0.0.0.0:0"), range));
+        assert!(is_udp_listener(&udp("127.0.0.1:40000", "0.0.0.0:0"), range));
+        assert!(!is_udp_listener(&udp("0.0.0.0:40000", "0.0.0.0:0"), range));
+        assert!(!is_udp_listener(&udp("[::]:0", "[::]:0"), range));
+        assert!(!is_udp_listener(&udp("10.0.0.2:53", "10.0.0.1:53"), range));
+    }
+
+    #[test]
+    fn connections_per_process() {
+        let sockets = read_sockets(&linux_parser::tests::fixture_root(), &linux_parser::tests::fixture_processes()).unwrap();
+        let counts = count_connections(&sockets);
+        assert_eq!(counts.get(&42), Some(&1));
+        assert_eq!(counts.get(&1), None);
+        // a connected unix socket isn't a network connection
+        assert!(sockets.iter().any(|socket| socket.protocol == Protocol::Unix && socket.connected));
+    }
+}
@@ -1,21 +1,50 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Write};
 
-use chrono::{DateTime, Utc};
+use chrono::Utc;
 
-use crate::linux_parser;
-use crate::process::Process;
+use crate::error::ParserError;
+use crate::filter::ProcessFilter;
+use crate::linux_parser::{self, FsRoot, LoadAvg, ProcessStates};
+use crate::process::{Process, SortKey, SortOrder};
+use crate::thread::{Thread, ThreadScope};
+use crate::cgroup::{self, CgroupUsage};
+use crate::container::ContainerResolver;
+use crate::socket::{self, Socket, SocketRow};
+use crate::tree::{self, TreeRow};
 use crate::format;
 use crate::cpu::Cpu;
 use crate::memory::MemInfo;
+use crate::disk::Disks;
+use crate::network::Network;
+use crate::pressure::Pressure;
 
+/// A snapshot of the whole system, updated in place by [`System::refresh`].
 #[derive(Debug)]
 pub struct System {
+    root: FsRoot,
     os: String,
     kernel: String,
     cpu: Cpu,
     memory: MemInfo,
+    disks: Disks,
+    network: Network,
+    pressure: Pressure,
     processes: Vec<Process>,
-    total_processes: u32,
+    unreadable_processes: u32,
+    sockets: Vec<Socket>,
+    thread_scope: ThreadScope,
+    threads: Vec<Thread>,
+    unreadable_threads: u32,
+    sort_key: SortKey,
+    sort_order: SortOrder,
+    filter: ProcessFilter,
+    container_resolver: Option<Box<dyn ContainerResolver>>,
     running_processes: u32,
+    forks: u64,
+    forks_per_second: f64,
+    load_avg: LoadAvg,
     uptime: f64,
 }
 
@@ -32,79 +61,482 @@ fn header() -> String {
     )
 }
 
+// reads every pid, skipping the processes that exited in the meantime and counting
+// the ones that can't be read or parsed, one bad process doesn't hide all the others
+fn read_processes(root: &FsRoot, uptime: f64) -> Result<(Vec<Process>, u32), ParserError> {
+    let mut processes: Vec<Process> = Vec::new();
+    let mut unreadable: u32 = 0;
+    let pids = linux_parser::get_pids(root)?;
+    for pid in pids {
+        match Process::new(root, pid, uptime) {
+            Ok(process) => processes.push(process),
+            Err(e) if e.is_vanished() => continue,
+            Err(_) => unreadable += 1,
+        }
+    }
+    Ok((processes, unreadable))
+}
+
+// reads the threads of `processes` like read_processes, skipping the ones that exited
+// and counting the ones that can't be read, e.g. another user's task directory
+fn read_threads(root: &FsRoot, processes: &[&Process], uptime: f64) -> (Vec<Thread>, u32) {
+    let mut threads: Vec<Thread> = Vec::new();
+    let mut unreadable: u32 = 0;
+    for process in processes {
+        let tids = match linux_parser::get_tids(root, process.pid()) {
+            Ok(tids) => tids,
+            Err(e) if e.is_vanished() => continue,
+            Err(_) => {
+                unreadable += 1;
+                continue;
+            }
+        };
+        for tid in tids {
+            match Thread::new(root, process, tid, uptime) {
+                Ok(thread) => threads.push(thread),
+                Err(e) if e.is_vanished() => continue,
+                Err(_) => unreadable += 1,
+            }
+        }
+    }
+    (threads, unreadable)
+}
+
 impl System {
-    pub fn new() -> System {
-        let os = linux_parser::get_operating_system();
-        let kernel = linux_parser::get_kernel();
+    /// Takes a first snapshot of the system found under `root`.
+    pub fn new(root: FsRoot) -> Result<System, ParserError> {
+        let os = linux_parser::get_operating_system(&root)?;
+        let kernel = linux_parser::get_kernel(&root)?;
         
-        let cpu = Cpu::new();
+        let cpu = Cpu::new(&root)?;
         
-        let memory = MemInfo::new();
+        let memory = MemInfo::new(&root)?;
+
+        let disks = Disks::new(&root)?;
+
+        let network = Network::new(&root)?;
+
+        let pressure = Pressure::new(&root)?;
         
-        let uptime = linux_parser::get_uptime();
+        let uptime = linux_parser::get_uptime(&root)?;
 
-        let total_processes = linux_parser::get_processes(linux_parser::ProcessStates::Total);
-        let running_processes = linux_parser::get_processes(linux_parser::ProcessStates::Running);
+        let running_processes = linux_parser::get_processes(&root, ProcessStates::Running)? as u32;
+        let forks = linux_parser::get_processes(&root, ProcessStates::Forks)?;
+        // until the first refresh, the average since boot
+        let forks_per_second = if uptime > 0.0 { forks as f64 / uptime } else { 0.0 };
+        let load_avg = linux_parser::get_load_avg(&root)?;
 
-        let mut processes: Vec<Process> = Vec::new();
-        let pids = linux_parser::get_pids();
-        for pid in pids {
-            processes.push(Process::new(pid, cpu.total_jif(), uptime));
-        }
-        processes.sort_by(|a, b| a.cpu_utilization().partial_cmp(&b.cpu_utilization()).unwrap());
-        processes.reverse();
+        let (processes, unreadable_processes) = read_processes(&root, uptime)?;
 
-        System {
+        let mut sys = System {
+            root,
             os,
             kernel,
             cpu,
             memory,
+            disks,
+            network,
+            pressure,
             processes,
-            total_processes,
+            unreadable_processes,
+            sockets: Vec::new(),
+            thread_scope: ThreadScope::None,
+            threads: Vec::new(),
+            unreadable_threads: 0,
+            sort_key: SortKey::Cpu,
+            sort_order: SortOrder::Descending,
+            filter: ProcessFilter::default(),
+            container_resolver: None,
             running_processes,
+            forks,
+            forks_per_second,
+            load_avg,
             uptime,
-        }
+        };
+        sys.read_sockets()?;
+        sys.sort_processes();
+        Ok(sys)
     }
 
 
-    pub fn refresh(&mut self) {
-        self.cpu.refresh();
+    /// Takes a new snapshot. Rates such as the CPU utilization are computed
+    /// over the time since the previous snapshot.
+    pub fn refresh(&mut self) -> Result<(), ParserError> {
+        self.cpu.refresh(&self.root)?;
 
-        self.memory.refresh();
+        self.memory.refresh(&self.root)?;
 
-        let uptime = linux_parser::get_uptime();
+        self.disks.refresh(&self.root)?;
 
-        let mut processes: Vec<Process> = Vec::new();
-        let pids = linux_parser::get_pids();
-        for pid in pids { processes.push(Process::new(pid, self.cpu.total_jif(), uptime)); }
+        self.network.refresh(&self.root)?;
+
+        self.pressure.refresh(&self.root)?;
+
+        let uptime = linux_parser::get_uptime(&self.root)?;
+
+        let previous: HashMap<(u32, u64), &Process> = self.processes
+            .iter()
+            .map(|process| (process.key(), process))
+            .collect();
+
+        let (mut processes, unreadable_processes) = read_processes(&self.root, uptime)?;
+        for process in processes.iter_mut() {
+            if let Some(prev) = previous.get(&process.key()) {
+                process.update_cpu_utilization(prev);
+                process.update_io_rates(prev);
+            }
+        }
         self.processes = processes;
+        self.unreadable_processes = unreadable_processes;
+        if let Some(resolver) = self.container_resolver.as_mut() {
+            resolver.refresh();
+        }
+        self.resolve_containers();
+        self.read_sockets()?;
 
-        self.total_processes = linux_parser::get_processes(linux_parser::ProcessStates::Total);
-        self.running_processes = linux_parser::get_processes(linux_parser::ProcessStates::Running);
+        let previous: HashMap<(u32, u64), &Thread> = self.threads
+            .iter()
+            .map(|thread| (thread.key(), thread))
+            .collect();
+
+        let (mut threads, unreadable_threads) = read_threads(&self.root, &self.thread_processes(), uptime);
+        for thread in threads.iter_mut() {
+            if let Some(prev) = previous.get(&thread.key()) {
+                thread.update_cpu_utilization(prev);
+            }
+        }
+        self.threads = threads;
+        self.unreadable_threads = unreadable_threads;
+        self.sort_processes();
+
+        self.running_processes = linux_parser::get_processes(&self.root, ProcessStates::Running)? as u32;
+        let forks = linux_parser::get_processes(&self.root, ProcessStates::Forks)?;
+        let seconds = uptime - self.uptime;
+        self.forks_per_second = if seconds > 0.0 {
+            forks.saturating_sub(self.forks) as f64 / seconds
+        } else {
+            0.0
+        };
+        self.forks = forks;
+        self.load_avg = linux_parser::get_load_avg(&self.root)?;
 
         self.uptime = uptime;
+        Ok(())
     }
 
-    pub fn print(&self, process_limit: u32) {
-        println!("{}", header());
-        println!("OS: {} Kernel: {}", self.os, self.kernel);
-        println!("Uptime: {}", format::format_seconds(self.uptime as u64));
-        println!("CPU");
-        println!("{}", self.cpu);
-        println!("Memory");
-        println!("{}", self.memory);
-        println!("Processes");
-        println!("- Total: {}, Running: {}", self.total_processes, self.running_processes);
-        println!("");
-        println!("{}", Process::head_str());
-        let mut process_cnt = 0;
-        for process in &self.processes {
-            //let process = process.to_string();
-            println!("{}", process);
-            process_cnt += 1;
-            if process_cnt >= process_limit {
-                break;
+    /// Sorts the processes by `key`, now and on every refresh.
+    pub fn set_sort(&mut self, key: SortKey, order: SortOrder) {
+        self.sort_key = key;
+        self.sort_order = order;
+        self.sort_processes();
+    }
+
+    pub fn sort(&self) -> (SortKey, SortOrder) { (self.sort_key, self.sort_order) }
+
+    fn sort_processes(&mut self) {
+        let (key, order) = self.sort();
+        self.processes.sort_by(|a, b| match order {
+            SortOrder::Ascending => a.compare(b, key),
+            SortOrder::Descending => b.compare(a, key),
+        });
+        self.threads.sort_by(|a, b| match order {
+            SortOrder::Ascending => a.compare(b, key),
+            SortOrder::Descending => b.compare(a, key),
+        });
+    }
+
+    /// Reads the threads of `scope` now and on every refresh. Reading every
+    /// thread is slower than reading processes, so this is off by default.
+    /// Threads that can't be read are left out and counted, see [`System::unreadable_threads`].
+    pub fn set_thread_scope(&mut self, scope: ThreadScope) {
+        self.thread_scope = scope;
+        let (threads, unreadable_threads) = read_threads(&self.root, &self.thread_processes(), self.uptime);
+        self.threads = threads;
+        self.unreadable_threads = unreadable_threads;
+        self.sort_processes();
+    }
+
+    pub fn thread_scope(&self) -> ThreadScope { self.thread_scope }
+
+    // the processes whose threads are read
+    fn thread_processes(&self) -> Vec<&Process> {
+        match self.thread_scope {
+            ThreadScope::None => vec![],
+            ThreadScope::Process(pid) => self.processes.iter().filter(|p| p.pid() == pid).collect(),
+            ThreadScope::All => self.filtered_processes(),
+        }
+    }
+
+    /// The sorted threads of the thread scope, empty unless [`System::set_thread_scope`] was called.
+    pub fn threads(&self) -> &[Thread] { &self.threads }
+
+    /// The filtered processes summed per cgroup, sorted like the processes.
+    pub fn cgroups(&self) -> Vec<CgroupUsage> {
+        let mut cgroups = cgroup::group_by_cgroup(&self.root, &self.filtered_processes());
+        let (key, order) = self.sort();
+        cgroups.sort_by(|a, b| match order {
+            SortOrder::Ascending => a.compare(b, key),
+            SortOrder::Descending => b.compare(a, key),
+        });
+        cgroups
+    }
+
+    /// Names the containers of the processes with `resolver`, now and on every refresh.
+    /// Without a resolver the short container IDs are shown.
+    pub fn set_container_resolver(&mut self, resolver: Box<dyn ContainerResolver>) {
+        self.container_resolver = Some(resolver);
+        self.resolve_containers();
+    }
+
+    // the sockets of every process and the connection counts of the processes
+    fn read_sockets(&mut self) -> Result<(), ParserError> {
+        self.sockets = socket::read_sockets(&self.root, &self.processes)?;
+        let connections = socket::count_connections(&self.sockets);
+        for process in self.processes.iter_mut() {
+            process.set_connections(&connections);
+        }
+        Ok(())
+    }
+
+    fn resolve_containers(&mut self) {
+        if let Some(resolver) = &self.container_resolver {
+            for process in self.processes.iter_mut() {
+                process.resolve_container(resolver.as_ref());
             }
         }
     }
-}
\ No newline at end of file
+
+    /// Only the processes matching `filter` are shown, the totals stay system wide.
+    pub fn set_filter(&mut self, filter: ProcessFilter) {
+        self.filter = filter;
+    }
+
+    pub fn filter(&self) -> &ProcessFilter { &self.filter }
+
+    /// The sorted processes that match the filter.
+    pub fn filtered_processes(&self) -> Vec<&Process> {
+        self.processes.iter().filter(|p| self.filter.matches(p)).collect()
+    }
+
+    /// The filtered processes arranged by parent pid, leaving out the children of `collapsed` pids.
+    pub fn process_tree(&self, collapsed: &HashSet<u32>) -> Vec<TreeRow<'_>> {
+        tree::build_tree(&self.filtered_processes(), collapsed)
+    }
+
+    /// The listening sockets of the filtered processes, by port, unix sockets last.
+    /// Sockets whose process isn't known are only listed without a filter.
+    pub fn listening_sockets(&self) -> Vec<SocketRow<'_>> {
+        let processes: HashMap<u32, &Process> = self.filtered_processes()
+            .into_iter()
+            .map(|process| (process.pid(), process))
+            .collect();
+        let mut rows: Vec<SocketRow> = self.sockets
+            .iter()
+            .filter(|socket| socket.listening)
+            .filter_map(|socket| {
+                let process = socket.pids.iter().find_map(|pid| processes.get(pid).copied());
+                if process.is_none() && !self.filter.is_empty() {
+                    return None;
+                }
+                Some(SocketRow { socket, process })
+            })
+            .collect();
+        rows.sort_by_key(|row| (row.socket.port.is_none(), row.socket.port));
+        rows
+    }
+
+    /// Every TCP, UDP and unix socket, in the order of the /proc/net tables.
+    pub fn sockets(&self) -> &[Socket] { &self.sockets }
+
+    pub fn root(&self) -> &FsRoot { &self.root }
+
+    pub fn os(&self) -> &str { &self.os }
+
+    pub fn kernel(&self) -> &str { &self.kernel }
+
+    pub fn cpu(&self) -> &Cpu { &self.cpu }
+
+    pub fn memory(&self) -> &MemInfo { &self.memory }
+
+    pub fn disks(&self) -> &Disks { &self.disks }
+
+    pub fn network(&self) -> &Network { &self.network }
+
+    pub fn pressure(&self) -> &Pressure { &self.pressure }
+
+    /// Shows or hides the loopback interface in the network panel.
+    pub fn set_show_loopback(&mut self, show: bool) {
+        self.network.set_show_loopback(show);
+    }
+
+    pub fn processes(&self) -> &[Process] { &self.processes }
+
+    /// Number of processes read in the last snapshot.
+    pub fn total_processes(&self) -> u32 { self.processes.len() as u32 }
+
+    /// Number of processes left out of the last snapshot because their files
+    /// couldn't be read or parsed, e.g. a malformed status file.
+    pub fn unreadable_processes(&self) -> u32 { self.unreadable_processes }
+
+    /// Number of threads of the thread scope left out because their files couldn't be read or parsed.
+    pub fn unreadable_threads(&self) -> u32 { self.unreadable_threads }
+
+    /// Threads running or ready to run, from /proc/stat.
+    pub fn running_processes(&self) -> u32 { self.running_processes }
+
+    /// New processes and threads per second since the previous snapshot.
+    pub fn forks_per_second(&self) -> f64 { self.forks_per_second }
+
+    pub fn load_avg(&self) -> &LoadAvg { &self.load_avg }
+
+    /// Seconds since boot.
+    pub fn uptime(&self) -> f64 { self.uptime }
+
+    /// Everything shown above the process table, one line per row.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{}\nOS: {} Kernel: {}\nUptime: {}\nLoad average: {:.2}, {:.2}, {:.2}  Tasks: {} runnable, {} total\nCPU\n{}\nMemory\n{}\nDisks\n{}\nNetwork\n{}\nPressure\n{}\nProcesses\n- Total: {}, Running: {}, Forks: {:.1}/s",
+            header(),
+            self.os,
+            self.kernel,
+            format::format_seconds(self.uptime as u64),
+            self.load_avg.one,
+            self.load_avg.five,
+            self.load_avg.fifteen,
+            self.load_avg.runnable,
+            self.load_avg.total,
+            self.cpu,
+            self.memory,
+            self.disks,
+            self.network,
+            self.pressure,
+            self.total_processes(),
+            self.running_processes,
+            self.forks_per_second,
+        );
+        if self.unreadable_processes > 0 {
+            summary += &format!(", Unreadable: {}", self.unreadable_processes);
+        }
+        if self.unreadable_threads > 0 {
+            summary += &format!(", Unreadable threads: {}", self.unreadable_threads);
+        }
+        if !self.filter.is_empty() {
+            summary += &format!(
+                "\n- Shown: {} ({})",
+                self.filtered_processes().len(),
+                self.filter,
+            );
+        }
+        summary
+    }
+
+    // the summary, a blank line, the header and the first `limit` rows, 0 for all rows
+    fn write_table<W, R>(&self, out: &mut W, head: String, rows: R, limit: usize) -> io::Result<()>
+    where
+        W: Write,
+        R: IntoIterator,
+        R::Item: fmt::Display,
+    {
+        writeln!(out, "{}", self.summary())?;
+        writeln!(out)?;
+        writeln!(out, "{}", head)?;
+        let limit = if limit == 0 { usize::MAX } else { limit };
+        for row in rows.into_iter().take(limit) {
+            writeln!(out, "{}", row)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the snapshot and the first `limit` processes to `out`, all of them for 0.
+    pub fn write_processes<W: Write>(&self, out: &mut W, limit: usize) -> io::Result<()> {
+        self.write_table(out, Process::head_str(), self.filtered_processes(), limit)
+    }
+
+    /// Like [`System::write_processes`], with the processes as a tree.
+    pub fn write_tree<W: Write>(&self, out: &mut W, limit: usize) -> io::Result<()> {
+        self.write_table(out, TreeRow::head_str(), self.process_tree(&HashSet::new()), limit)
+    }
+
+    /// Like [`System::write_processes`], with the processes summed per cgroup.
+    pub fn write_cgroups<W: Write>(&self, out: &mut W, limit: usize) -> io::Result<()> {
+        self.write_table(out, CgroupUsage::head_str(), self.cgroups(), limit)
+    }
+
+    /// Like [`System::write_processes`], with the listening sockets instead of the processes.
+    pub fn write_listening<W: Write>(&self, out: &mut W, limit: usize) -> io::Result<()> {
+        self.write_table(out, SocketRow::head_str(), self.listening_sockets(), limit)
+    }
+
+    /// Like [`System::write_processes`], with the threads of the thread scope instead of the processes.
+    pub fn write_threads<W: Write>(&self, out: &mut W, limit: usize) -> io::Result<()> {
+        self.write_table(out, Thread::head_str(), &self.threads, limit)
+    }
+
+    /// Like [`System::print`], with the processes as a tree.
+    pub fn print_tree(&self, process_limit: u32) {
+        let _ = self.write_tree(&mut io::stdout(), process_limit as usize);
+    }
+
+    /// Like [`System::print`], with the processes summed per cgroup.
+    pub fn print_cgroups(&self, cgroup_limit: u32) {
+        let _ = self.write_cgroups(&mut io::stdout(), cgroup_limit as usize);
+    }
+
+    /// Like [`System::print`], with the listening sockets instead of the processes.
+    pub fn print_listening(&self, socket_limit: u32) {
+        let _ = self.write_listening(&mut io::stdout(), socket_limit as usize);
+    }
+
+    /// Like [`System::print`], with the threads of the thread scope instead of the processes.
+    pub fn print_threads(&self, thread_limit: u32) {
+        let _ = self.write_threads(&mut io::stdout(), thread_limit as usize);
+    }
+
+    /// Prints the snapshot and the first `process_limit` processes to stdout.
+    pub fn print(&self, process_limit: u32) {
+        let _ = self.write_processes(&mut io::stdout(), process_limit as usize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn unreadable_process_is_skipped() {
+        let (processes, unreadable) = read_processes(&linux_parser::tests::minimal_root(), 1000.0).unwrap();
+        let pids: Vec<u32> = processes.iter().map(|p| p.pid()).collect();
+        assert_eq!(pids, vec![1]);
+        assert_eq!(unreadable, 1);
+    }
+
+    #[test]
+    fn without_optional_proc_files() {
+        // no diskstats, net or pressure, e.g. in a sandbox
+        let mut sys = System::new(linux_parser::tests::minimal_root()).unwrap();
+        sys.refresh().unwrap();
+        assert!(!sys.disks().is_available());
+        assert!(!sys.network().is_available());
+        assert_eq!(sys.total_processes(), 1);
+        assert!(sys.summary().contains("- Total: 1, Running: 2, Forks: 0.0/s, Unreadable: 1"));
+    }
+
+    #[test]
+    fn unreadable_thread_is_skipped() {
+        let root = linux_parser::tests::minimal_root();
+        let process = Process::new(&root, 1, 1000.0).unwrap();
+        // task 9 has a truncated stat
+        let (threads, unreadable) = read_threads(&root, &[&process], 1000.0);
+        let tids: Vec<u32> = threads.iter().map(|t| t.tid()).collect();
+        assert_eq!(tids, vec![1]);
+        assert_eq!(unreadable, 1);
+
+        let mut sys = System::new(root).unwrap();
+        sys.set_thread_scope(ThreadScope::All);
+        sys.refresh().unwrap();
+        assert_eq!(sys.threads().len(), 1);
+        assert_eq!(sys.unreadable_threads(), 1);
+        assert!(sys.summary().contains(", Unreadable threads: 1"));
+    }
+}
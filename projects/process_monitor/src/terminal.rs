@@ -1,9 +1,38 @@
+use std::io;
+use std::panic;
 
+use crossterm::{cursor, execute, terminal};
 
+// raw mode on the alternate screen, the terminal is restored when dropped
+pub struct Screen;
 
-pub fn clear_screen() {
-    print!("{}[2J", 27 as char);
+impl Screen {
+    pub fn enter() -> io::Result<Screen> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        // restore the terminal before the panic message gets printed
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore();
+            hook(info);
+        }));
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        restore();
+    }
 }
 
-// get window size with
-// https://stackoverflow.com/questions/58892528/get-console-width-in-rust
\ No newline at end of file
+fn restore() {
+    let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+}
+
+// (columns, rows), with a sane default when stdout isn't a terminal
+pub fn size() -> (u16, u16) {
+    terminal::size().unwrap_or((80, 24))
+}
@@ -0,0 +1,209 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::error::ParserError;
+use crate::file_utils;
+use crate::linux_parser::{self, FsRoot};
+use crate::process::{self, Process, SortKey};
+use crate::format;
+
+/// Which threads [`crate::System`] reads on each refresh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadScope {
+    /// No threads, only processes.
+    None,
+    /// The threads of one process.
+    Process(u32),
+    /// The threads of every process matching the filter.
+    All,
+}
+
+/// One thread of a process, read from /proc/[pid]/task/[tid].
+#[derive(Debug, PartialEq)]
+pub struct Thread {
+    tid: u32,
+    pid: u32,
+    name: String,
+    user: String,
+    state: String,
+    nice: i32,
+    cpus_allowed: String,
+    acive_jiffies: u64,
+    start_time: u64,
+    sampled_at: f64,
+    uptime: u64,
+    cpu_utilization: f64,
+}
+
+impl Thread {
+
+    // the thread name, which can be changed with prctl(PR_SET_NAME) or pthread_setname_np
+    fn get_name(root: &FsRoot, pid: u32, tid: u32) -> Result<String, ParserError> {
+        let file_path = root.task_path(pid, tid, linux_parser::COMM_FILENAME);
+        let name = linux_parser::read_to_string(&file_path)?;
+        Ok(name.trim_end().to_string())
+    }
+
+    // each thread has its own affinity, left empty if the status can't be read
+    fn get_cpus_allowed(root: &FsRoot, pid: u32, tid: u32) -> String {
+        let file_path = root.task_path(pid, tid, linux_parser::STATUS_FILENAME);
+        file_utils::read_as_hashmap(&file_path, ":")
+            .map(|status| Process::get_cpus_allowed(&status))
+            .unwrap_or_default()
+    }
+
+    /// Reads the thread `tid` of `process`, `system_uptime` being the seconds since boot.
+    /// Until [`Thread::update_cpu_utilization`] is called, the CPU% is the lifetime average.
+    pub fn new(root: &FsRoot, process: &Process, tid: u32, system_uptime: f64) -> Result<Thread, ParserError> {
+        let pid = process.pid();
+        let stat = linux_parser::get_task_stat(root, pid, tid)?;
+        let name = Thread::get_name(root, pid, tid)?;
+        let cpus_allowed = Thread::get_cpus_allowed(root, pid, tid);
+        let acive_jiffies = Process::get_active_jiffies(&stat);
+        let start_time = stat.start_time;
+
+        let clk_per_sec = linux_parser::get_sc_clk_tck() as f64;
+        let seconds = (system_uptime - start_time as f64 / clk_per_sec).max(0.0);
+        let cpu_utilization = process::cpu_percent(acive_jiffies, seconds, clk_per_sec);
+
+        Ok(Thread {
+            tid,
+            pid,
+            name,
+            // threads share the credentials of their process
+            user: process.user(),
+            state: stat.state,
+            nice: stat.nice,
+            cpus_allowed,
+            acive_jiffies,
+            start_time,
+            sampled_at: system_uptime,
+            uptime: seconds as u64,
+            cpu_utilization,
+        })
+    }
+
+    /// A tid can be reused, so threads are matched across refreshes by tid and start time.
+    pub fn key(&self) -> (u32, u64) { (self.tid, self.start_time) }
+
+    /// Sets the CPU% to the usage over the interval since `previous` was sampled.
+    pub fn update_cpu_utilization(&mut self, previous: &Thread) {
+        if previous.key() != self.key() {
+            return;
+        }
+        let clk_per_sec = linux_parser::get_sc_clk_tck() as f64;
+        let jiffies = self.acive_jiffies.saturating_sub(previous.acive_jiffies);
+        let seconds = self.sampled_at - previous.sampled_at;
+        self.cpu_utilization = process::cpu_percent(jiffies, seconds, clk_per_sec);
+    }
+
+    pub fn tid(&self) -> u32 { self.tid }
+
+    /// Pid of the process the thread belongs to, equal to the tid for the main thread.
+    pub fn pid(&self) -> u32 { self.pid }
+
+    /// The thread name from /proc/[pid]/task/[tid]/comm.
+    pub fn name(&self) -> &str { &self.name }
+
+    pub fn user(&self) -> String { self.user.clone() }
+
+    /// Single letter state, e.g. R for running or S for sleeping.
+    pub fn state(&self) -> &str { &self.state }
+
+    /// Each thread has its own nice value, from 19 (lowest priority) to -20 (highest priority).
+    pub fn nice(&self) -> i32 { self.nice }
+
+    /// The CPUs the thread may run on, e.g. `0-3,8`, which can differ from the ones of its process.
+    pub fn cpus_allowed(&self) -> &str { &self.cpus_allowed }
+
+    /// Seconds since the thread started.
+    pub fn uptime(&self) -> u64 { self.uptime }
+
+    /// CPU% of one core.
+    pub fn cpu_utilization(&self) -> f64 { self.cpu_utilization }
+
+    /// Orders threads by `key`, lowest first. The pid column sorts by tid and
+    /// the command column by thread name, the columns threads don't have by pid.
+    pub fn compare(&self, other: &Thread, key: SortKey) -> Ordering {
+        match key {
+            SortKey::Pid => self.tid.cmp(&other.tid),
+            SortKey::User => self.user.cmp(&other.user),
+            SortKey::Cpu => self.cpu_utilization.partial_cmp(&other.cpu_utilization).unwrap_or(Ordering::Equal),
+            SortKey::Ram | SortKey::Threads | SortKey::IoRead | SortKey::IoWrite | SortKey::Fds | SortKey::Connections => {
+                self.pid.cmp(&other.pid)
+            }
+            SortKey::Nice => self.nice.cmp(&other.nice),
+            SortKey::Uptime => self.uptime.cmp(&other.uptime),
+            SortKey::Command => self.name.cmp(&other.name),
+        }
+    }
+
+    pub fn head_str() -> String {
+        format!(
+            "{:<8} {:<8} {:<8} {:<8} {:<5} {:>3} {:>10} {:<16}",
+            "TID",
+            "PID",
+            "USER",
+            "CPU[%]",
+            "STATE",
+            "NI",
+            "UPTIME",
+            "NAME",
+        )
+    }
+}
+
+impl fmt::Display for Thread {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "{:<8} {:<8} {:<8} {:<8.2} {:<5} {:>3} {:>10} {:<16}",
+            self.tid,
+            self.pid,
+            self.user,
+            self.cpu_utilization,
+            self.state,
+            self.nice,
+            format::format_seconds(self.uptime),
+            self.name,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn fixture_threads() {
+        let root = linux_parser::tests::fixture_root();
+        let process = Process::new(&root, 42, 1000.5).unwrap();
+        let mut tids = linux_parser::get_tids(&root, 42).unwrap();
+        tids.sort_unstable();
+        assert_eq!(tids, vec![42, 43]);
+
+        let worker = Thread::new(&root, &process, 43, 1000.5).unwrap();
+        assert_eq!(worker.pid(), 42);
+        assert_eq!(worker.name(), "worker-0");
+        assert_eq!(worker.user(), "alice");
+        assert_eq!(worker.state(), "R");
+        assert_eq!(worker.nice(), 10);
+        assert_eq!(worker.cpus_allowed(), "1");
+    }
+
+    #[test]
+    fn interval_cpu_utilization() {
+        let root = linux_parser::tests::fixture_root();
+        let process = Process::new(&root, 42, 1000.5).unwrap();
+        let clk_per_sec = linux_parser::get_sc_clk_tck() as u64;
+        let mut thread = Thread::new(&root, &process, 43, 1000.5).unwrap();
+        let previous = Thread {
+            acive_jiffies: thread.acive_jiffies - clk_per_sec,
+            sampled_at: 999.5,
+            ..Thread::new(&root, &process, 43, 999.5).unwrap()
+        };
+        thread.update_cpu_utilization(&previous);
+        // one second of CPU time over a one second interval
+        assert_eq!(thread.cpu_utilization(), 100.0);
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::format;
+use crate::process::Process;
+
+/// One line of the process tree, in display order.
+#[derive(Debug)]
+pub struct TreeRow<'a> {
+    pub process: &'a Process,
+    pub depth: usize,
+    /// Branch drawing in front of the command, e.g. "│  ├─ ".
+    pub prefix: String,
+    pub children: usize,
+    pub collapsed: bool,
+    /// CPU% of the process and all its descendants.
+    pub subtree_cpu: f64,
+    /// RAM in kB of the process and all its descendants.
+    pub subtree_ram: u64,
+}
+
+impl TreeRow<'_> {
+    pub fn head_str() -> String {
+        format!(
+            "{:<8} {:<8} {:<8} {:<10} {:>5} {:>3} {:<8} {:<10} {:>10} {:<40}",
+            "PID",
+            "USER",
+            "CPU[%]",
+            "RAM[KB]",
+            "THR",
+            "NI",
+            "\u{3a3}CPU[%]",
+            "\u{3a3}RAM[KB]",
+            "UPTIME",
+            "COMMAND",
+        )
+    }
+}
+
+impl fmt::Display for TreeRow<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let marker = if self.collapsed && self.children > 0 { "[+] " } else { "" };
+        write!(
+            f, "{:<8} {:<8} {:<8.2} {:<10} {:>5} {:>3} {:<8.2} {:<10} {:>10} {}{}{}",
+            self.process.pid(),
+            self.process.user(),
+            self.process.cpu_utilization(),
+            self.process.ram(),
+            self.process.threads(),
+            self.process.nice(),
+            self.subtree_cpu,
+            self.subtree_ram,
+            format::format_seconds(self.process.uptime()),
+            self.prefix,
+            marker,
+            self.process.short_command()
+        )
+    }
+}
+
+/// Arranges `processes` by parent pid. Processes whose parent isn't in the list
+/// are roots, siblings keep the order of the list and the children of a pid in
+/// `collapsed` are left out.
+pub fn build_tree<'a>(processes: &[&'a Process], collapsed: &HashSet<u32>) -> Vec<TreeRow<'a>> {
+    let index: HashMap<u32, usize> = processes
+        .iter()
+        .enumerate()
+        .map(|(i, process)| (process.pid(), i))
+        .collect();
+
+    let mut children: Vec<Vec<usize>> = vec![vec![]; processes.len()];
+    let mut roots: Vec<usize> = vec![];
+    for (i, process) in processes.iter().enumerate() {
+        match index.get(&process.ppid()) {
+            Some(parent) if *parent != i => children[*parent].push(i),
+            _ => roots.push(i),
+        }
+    }
+
+    let mut totals: Vec<Option<(f64, u64)>> = vec![None; processes.len()];
+    let mut rows: Vec<TreeRow> = vec![];
+    for root in roots {
+        add_rows(root, 0, String::new(), String::new(), processes, &children, collapsed, &mut totals, &mut rows);
+    }
+    rows
+}
+
+#[allow(clippy::too_many_arguments)]
+fn add_rows<'a>(
+    i: usize,
+    depth: usize,
+    prefix: String,
+    continuation: String,
+    processes: &[&'a Process],
+    children: &[Vec<usize>],
+    collapsed: &HashSet<u32>,
+    totals: &mut [Option<(f64, u64)>],
+    rows: &mut Vec<TreeRow<'a>>,
+) {
+    let process = processes[i];
+    let (subtree_cpu, subtree_ram) = subtree_totals(i, processes, children, totals);
+    let is_collapsed = collapsed.contains(&process.pid());
+    rows.push(TreeRow {
+        process,
+        depth,
+        prefix,
+        children: children[i].len(),
+        collapsed: is_collapsed,
+        subtree_cpu,
+        subtree_ram,
+    });
+    if is_collapsed {
+        return;
+    }
+
+    for (n, child) in children[i].iter().enumerate() {
+        let last = n + 1 == children[i].len();
+        let branch = if last { "\u{2514}\u{2500} " } else { "\u{251c}\u{2500} " };
+        let indent = if last { "   " } else { "\u{2502}  " };
+        add_rows(
+            *child,
+            depth + 1,
+            format!("{}{}", continuation, branch),
+            format!("{}{}", continuation, indent),
+            processes,
+            children,
+            collapsed,
+            totals,
+            rows,
+        );
+    }
+}
+
+fn subtree_totals(
+    i: usize,
+    processes: &[&Process],
+    children: &[Vec<usize>],
+    totals: &mut [Option<(f64, u64)>],
+) -> (f64, u64) {
+    if let Some(total) = totals[i] {
+        return total;
+    }
+    let mut cpu = processes[i].cpu_utilization();
+    let mut ram = processes[i].ram() as u64;
+    for child in &children[i] {
+        let (child_cpu, child_ram) = subtree_totals(*child, processes, children, totals);
+        cpu += child_cpu;
+        ram += child_ram;
+    }
+    totals[i] = Some((cpu, ram));
+    (cpu, ram)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::linux_parser;
+
+    #[test]
+    fn children_under_parents() {
+        let processes = linux_parser::tests::fixture_processes();
+        // by pid descending, so the roots keep that order
+        let processes: Vec<&Process> = processes.iter().rev().collect();
+        let rows = build_tree(&processes, &HashSet::new());
+
+        let layout: Vec<(u32, usize)> = rows.iter().map(|r| (r.process.pid(), r.depth)).collect();
+        assert_eq!(layout, vec![(2, 0), (1, 0), (42, 1)]);
+        assert_eq!(rows[2].prefix, "\u{2514}\u{2500} ");
+        // init's subtree includes the python worker
+        assert_eq!(rows[1].subtree_ram, 4000 + 16000);
+        assert_eq!(rows[1].children, 1);
+    }
+
+    #[test]
+    fn collapsed_subtree_keeps_totals() {
+        let processes = linux_parser::tests::fixture_processes();
+        // by pid descending, so the roots keep that order
+        let processes: Vec<&Process> = processes.iter().rev().collect();
+        let collapsed: HashSet<u32> = [1].iter().copied().collect();
+        let rows = build_tree(&processes, &collapsed);
+
+        let pids: Vec<u32> = rows.iter().map(|r| r.process.pid()).collect();
+        assert_eq!(pids, vec![2, 1]);
+        assert_eq!(rows[1].subtree_ram, 20000);
+        assert!(rows[1].to_string().contains("[+] /sbin/init"));
+    }
+
+    #[test]
+    fn orphans_become_roots() {
+        let processes = linux_parser::tests::fixture_processes();
+        let processes: Vec<&Process> = processes.iter().filter(|p| p.pid() != 1).collect();
+        let rows = build_tree(&processes, &HashSet::new());
+        assert!(rows.iter().all(|r| r.depth == 0));
+    }
+}
@@ -0,0 +1,1053 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, queue, style, terminal};
+
+use process_monitor::control::{self, Signal};
+use process_monitor::filter::{self, CommandPattern};
+use process_monitor::linux_parser;
+use process_monitor::fd;
+use process_monitor::{
+    CgroupUsage, ControlError, OpenFd, Process, SocketRow, SortOrder, System, Thread, ThreadScope, TreeRow,
+};
+
+use crate::config::Config;
+use crate::terminal::{self as term, Screen};
+
+const MIN_DELAY_MILLIS: u64 = 500;
+const MAX_DELAY_MILLIS: u64 = 10000;
+const DELAY_STEP_MILLIS: u64 = 500;
+
+// what the text typed at the bottom line is for
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Prompt {
+    User,
+    Pid,
+    Command,
+    CommandRegex,
+    Container,
+    Signal(SignalTarget),
+    Nice,
+    Affinity,
+}
+
+// which processes a signal is sent to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SignalTarget {
+    Selected,
+    // every process matching the filter
+    Shown,
+}
+
+impl Prompt {
+    fn label(&self) -> &str {
+        match self {
+            Prompt::User => "users (name or uid, comma separated)",
+            Prompt::Pid => "pids (comma separated)",
+            Prompt::Command => "command contains",
+            Prompt::CommandRegex => "command matches regex",
+            Prompt::Container => "containers (name or ID, comma separated)",
+            Prompt::Signal(SignalTarget::Selected) => "signal for the selected process (TERM, KILL, STOP, CONT, name or number)",
+            Prompt::Signal(SignalTarget::Shown) => "signal for all shown processes (TERM, KILL, STOP, CONT, name or number)",
+            Prompt::Nice => "nice value (-20 to 19)",
+            Prompt::Affinity => "CPUs to run on (e.g. 0-3,8)",
+        }
+    }
+}
+
+// how the processes are laid out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum View {
+    List,
+    Tree,
+    // the threads of the system's thread scope instead of processes
+    Threads,
+    // the processes summed per cgroup, rows are picked by their index
+    Cgroups,
+    // the listening sockets and their processes, rows are picked by their index
+    Listening,
+    // the open files of a process, rows are picked by their fd
+    Fds(u32),
+}
+
+pub struct App {
+    sys: System,
+    view: View,
+    // the view to go back to from the thread, cgroup or fd view
+    process_view: View,
+    // the open files shown in the fd view, read again on every refresh
+    fds: Vec<OpenFd>,
+    // pids whose children are hidden in the tree view
+    collapsed: HashSet<u32>,
+    delay: Duration,
+    paused: bool,
+    input: Option<(Prompt, String)>,
+    // a signal waiting for y/n before it is sent to the processes with these (pid, start time) keys
+    confirm: Option<(Signal, Vec<(u32, u64)>)>,
+    // the pids of the proc root are the ones of our pid namespace and can be signaled, reniced and pinned
+    local_pids: bool,
+    // shown in the status line until the next key press
+    message: Option<String>,
+    // index into the sorted process list, kept on the same pid (or tid) across refreshes
+    selected: usize,
+    selected_pid: Option<u32>,
+    offset: usize,
+    page_size: usize,
+    quit: bool,
+}
+
+impl App {
+    /// Starts in `view`, or in the thread view if `sys` already reads threads.
+    pub fn new(sys: System, delay_millis: u64, view: View) -> App {
+        let process_view = if view == View::Tree { View::Tree } else { View::List };
+        let view = match sys.thread_scope() {
+            ThreadScope::None => view,
+            _ => View::Threads,
+        };
+        let local_pids = control::is_local_root(sys.root());
+        App {
+            sys,
+            view,
+            process_view,
+            fds: Vec::new(),
+            collapsed: HashSet::new(),
+            delay: Duration::from_millis(delay_millis.clamp(MIN_DELAY_MILLIS, MAX_DELAY_MILLIS)),
+            paused: false,
+            input: None,
+            confirm: None,
+            local_pids,
+            message: None,
+            selected: 0,
+            selected_pid: None,
+            offset: 0,
+            page_size: 10,
+            quit: false,
+        }
+    }
+
+    fn table_head(&self) -> String {
+        match self.view {
+            View::List => Process::head_str(),
+            View::Tree => TreeRow::head_str(),
+            View::Threads => Thread::head_str(),
+            View::Cgroups => CgroupUsage::head_str(),
+            View::Listening => SocketRow::head_str(),
+            View::Fds(_) => OpenFd::head_str(),
+        }
+    }
+
+    // (pid, text, near the fd limit) of every line of the table, the selection moves over these
+    fn rows(&self) -> Vec<(u32, String, bool)> {
+        match self.view {
+            View::List => self.sys
+                .filtered_processes()
+                .iter()
+                .map(|p| (p.pid(), p.to_string(), p.is_near_fd_limit()))
+                .collect(),
+            View::Tree => self.sys
+                .process_tree(&self.collapsed)
+                .iter()
+                .map(|row| (row.process.pid(), row.to_string(), row.process.is_near_fd_limit()))
+                .collect(),
+            View::Threads => self.sys
+                .threads()
+                .iter()
+                .map(|t| (t.tid(), t.to_string(), false))
+                .collect(),
+            View::Cgroups => self.sys
+                .cgroups()
+                .iter()
+                .enumerate()
+                .map(|(i, cgroup)| (i as u32, cgroup.to_string(), false))
+                .collect(),
+            View::Listening => self.sys
+                .listening_sockets()
+                .iter()
+                .enumerate()
+                .map(|(i, row)| (i as u32, row.to_string(), false))
+                .collect(),
+            View::Fds(_) => self.fds
+                .iter()
+                .map(|fd| (fd.fd, fd.to_string(), false))
+                .collect(),
+        }
+    }
+
+    fn pids(&self) -> Vec<u32> {
+        self.rows().iter().map(|(pid, _, _)| *pid).collect()
+    }
+
+    fn refresh(&mut self) -> Result<(), Box<dyn Error>> {
+        self.sys.refresh()?;
+        if let View::Fds(pid) = self.view {
+            self.read_fds(pid);
+        }
+        self.follow_selected_pid();
+        Ok(())
+    }
+
+    // move the selection to where the selected process ended up
+    fn follow_selected_pid(&mut self) {
+        let pids = self.pids();
+        if let Some(pid) = self.selected_pid {
+            if let Some(i) = pids.iter().position(|p| *p == pid) {
+                self.selected = i;
+            }
+        }
+        self.select(self.selected);
+    }
+
+    fn select(&mut self, index: usize) {
+        let pids = self.pids();
+        self.selected = index.min(pids.len().saturating_sub(1));
+        self.selected_pid = pids.get(self.selected).copied();
+    }
+
+    fn set_collapsed(&mut self, collapsed: bool) {
+        if let Some(pid) = self.selected_pid {
+            if collapsed {
+                self.collapsed.insert(pid);
+            } else {
+                self.collapsed.remove(&pid);
+            }
+        }
+        self.follow_selected_pid();
+    }
+
+    fn show_threads(&mut self, scope: ThreadScope) {
+        self.sys.set_thread_scope(scope);
+        if self.view != View::Threads {
+            self.process_view = self.view;
+        }
+        self.view = View::Threads;
+        self.select(0);
+    }
+
+    // back to the process the threads were shown for
+    fn hide_threads(&mut self) {
+        if let ThreadScope::Process(pid) = self.sys.thread_scope() {
+            self.selected_pid = Some(pid);
+        }
+        self.sys.set_thread_scope(ThreadScope::None);
+        self.view = self.process_view;
+        self.follow_selected_pid();
+    }
+
+    // the selection is a pid or tid that can be signaled, reniced or pinned
+    fn selects_task(&self) -> bool {
+        self.selected_pid.is_some() && matches!(self.view, View::List | View::Tree | View::Threads)
+    }
+
+    fn is_process_view(&self) -> bool {
+        matches!(self.view, View::List | View::Tree)
+    }
+
+    // keeps the previous list if the process can't be read anymore
+    fn read_fds(&mut self, pid: u32) -> bool {
+        match fd::read_fds(self.sys.root(), pid) {
+            Ok(fds) => {
+                self.fds = fds;
+                true
+            }
+            Err(e) => {
+                self.message = Some(e.to_string());
+                false
+            }
+        }
+    }
+
+    fn show_fds(&mut self, pid: u32) {
+        if !self.read_fds(pid) {
+            return;
+        }
+        self.process_view = self.view;
+        self.view = View::Fds(pid);
+        self.select(0);
+    }
+
+    fn hide_fds(&mut self) {
+        if let View::Fds(pid) = self.view {
+            self.selected_pid = Some(pid);
+        }
+        self.fds.clear();
+        self.view = self.process_view;
+        self.follow_selected_pid();
+    }
+
+    // the cgroup and listening views sum up or look past the processes
+    fn is_summary_view(&self) -> bool {
+        matches!(self.view, View::Cgroups | View::Listening)
+    }
+
+    // switches between a summary view and the process list or tree
+    fn toggle_view(&mut self, view: View) {
+        if self.view == view {
+            // a summary view can be where the thread view came from
+            self.view = match self.process_view {
+                View::List | View::Tree => self.process_view,
+                _ => View::List,
+            };
+            self.follow_selected_pid();
+        } else {
+            if self.is_process_view() {
+                self.process_view = self.view;
+            }
+            self.view = view;
+            self.select(0);
+        }
+    }
+
+    // the filter decides which threads the all threads view reads
+    fn filter_changed(&mut self) {
+        if self.sys.thread_scope() == ThreadScope::All {
+            self.sys.set_thread_scope(ThreadScope::All);
+        }
+        self.follow_selected_pid();
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        self.message = None;
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.quit = true;
+            return;
+        }
+        if let Some((signal, keys)) = self.confirm.take() {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => self.send_signal(signal, &keys),
+                _ => self.message = Some(String::from("Cancelled")),
+            }
+            return;
+        }
+        if self.input.is_some() {
+            self.handle_input_key(key);
+            return;
+        }
+
+        let page = self.page_size.max(1);
+        match key.code {
+            KeyCode::Esc if self.view == View::Threads => self.hide_threads(),
+            KeyCode::Esc | KeyCode::Char('f') if matches!(self.view, View::Fds(_)) => self.hide_fds(),
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('p') | KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected + 1),
+            KeyCode::PageUp => self.select(self.selected.saturating_sub(page)),
+            KeyCode::PageDown => self.select(self.selected + page),
+            KeyCode::Home => self.select(0),
+            KeyCode::End => self.select(usize::MAX),
+            KeyCode::Char('>') | KeyCode::Char('s') => {
+                let (key, order) = self.sys.sort();
+                self.sys.set_sort(key.next(), order);
+                self.follow_selected_pid();
+            }
+            KeyCode::Char('<') => {
+                let (key, order) = self.sys.sort();
+                self.sys.set_sort(key.previous(), order);
+                self.follow_selected_pid();
+            }
+            KeyCode::Char('r') => {
+                let (key, order) = self.sys.sort();
+                self.sys.set_sort(key, order.reversed());
+                self.follow_selected_pid();
+            }
+            KeyCode::Char('+') => self.change_delay(DELAY_STEP_MILLIS as i64),
+            KeyCode::Char('-') => self.change_delay(-(DELAY_STEP_MILLIS as i64)),
+            KeyCode::Char('u') => self.start_input(Prompt::User),
+            KeyCode::Char('#') => self.start_input(Prompt::Pid),
+            KeyCode::Char('/') => self.start_input(Prompt::Command),
+            KeyCode::Char('\\') => self.start_input(Prompt::CommandRegex),
+            KeyCode::Char('c') => self.start_input(Prompt::Container),
+            KeyCode::Char('K') | KeyCode::Char('A') if !self.local_pids => {
+                self.message = Some(self.foreign_pids_message("send signals"))
+            }
+            KeyCode::Char('K') if self.selects_task() => {
+                self.start_input(Prompt::Signal(SignalTarget::Selected))
+            }
+            // signaling every process needs a filter to narrow them down first
+            KeyCode::Char('A') if self.sys.filter().is_empty() => {
+                self.message = Some(String::from("Set a filter to signal all shown processes"))
+            }
+            KeyCode::Char('A') => self.start_input(Prompt::Signal(SignalTarget::Shown)),
+            // like signals, renicing and pinning act on the pids of our own namespace
+            KeyCode::Char('n') if !self.local_pids => self.message = Some(self.foreign_pids_message("renice")),
+            KeyCode::Char('a') if !self.local_pids => {
+                self.message = Some(self.foreign_pids_message("change the CPU affinity"))
+            }
+            KeyCode::Char('n') if self.selects_task() => self.start_input(Prompt::Nice),
+            KeyCode::Char('a') if self.selects_task() => self.start_input(Prompt::Affinity),
+            KeyCode::Char('t') if self.is_process_view() => {
+                self.view = match self.view {
+                    View::List => View::Tree,
+                    _ => View::List,
+                };
+                self.follow_selected_pid();
+            }
+            KeyCode::Char('g') if self.is_process_view() || self.is_summary_view() => self.toggle_view(View::Cgroups),
+            KeyCode::Char('L') if self.is_process_view() || self.is_summary_view() => self.toggle_view(View::Listening),
+            KeyCode::Char('T') if self.is_process_view() => {
+                if let Some(pid) = self.selected_pid {
+                    self.show_threads(ThreadScope::Process(pid));
+                }
+            }
+            KeyCode::Char('f') if self.is_process_view() => {
+                if let Some(pid) = self.selected_pid {
+                    self.show_fds(pid);
+                }
+            }
+            KeyCode::Char('H') if self.view == View::Threads => self.hide_threads(),
+            KeyCode::Char('H') if !matches!(self.view, View::Fds(_)) => self.show_threads(ThreadScope::All),
+            KeyCode::Left if self.view == View::Tree => self.set_collapsed(true),
+            KeyCode::Right if self.view == View::Tree => self.set_collapsed(false),
+            KeyCode::Enter if self.view == View::Tree => {
+                let collapsed = self.selected_pid.is_some_and(|pid| self.collapsed.contains(&pid));
+                self.set_collapsed(!collapsed);
+            }
+            KeyCode::Char('l') => {
+                let show = self.sys.network().show_loopback();
+                self.sys.set_show_loopback(!show);
+            }
+            KeyCode::Char('x') => {
+                self.sys.set_filter(Default::default());
+                self.filter_changed();
+            }
+            _ => {}
+        }
+    }
+
+    // starts editing the current value of a filter or of the selected process
+    fn start_input(&mut self, prompt: Prompt) {
+        let filter = self.sys.filter();
+        let selected = self.sys.processes().iter().find(|p| Some(p.pid()) == self.selected_pid);
+        let selected_thread = self.sys.threads().iter().find(|t| Some(t.tid()) == self.selected_pid);
+        let text = match prompt {
+            Prompt::User => filter.users.join(","),
+            Prompt::Pid => {
+                let pids: Vec<String> = filter.pids.iter().map(|pid| pid.to_string()).collect();
+                pids.join(",")
+            }
+            Prompt::Command => match &filter.command {
+                Some(CommandPattern::Substring(text)) => text.clone(),
+                _ => String::new(),
+            },
+            Prompt::CommandRegex => match &filter.command {
+                Some(CommandPattern::Regex(regex)) => regex.to_string(),
+                _ => String::new(),
+            },
+            Prompt::Container => filter.containers.join(","),
+            Prompt::Signal(_) => String::from("TERM"),
+            Prompt::Nice if self.view == View::Threads => {
+                selected_thread.map(|t| t.nice().to_string()).unwrap_or_default()
+            }
+            Prompt::Nice => selected.map(|p| p.nice().to_string()).unwrap_or_default(),
+            Prompt::Affinity if self.view == View::Threads => {
+                selected_thread.map(|t| t.cpus_allowed().to_string()).unwrap_or_default()
+            }
+            Prompt::Affinity => selected.map(|p| p.cpus_allowed().to_string()).unwrap_or_default(),
+        };
+        self.input = Some((prompt, text));
+    }
+
+    fn handle_input_key(&mut self, key: KeyEvent) {
+        let (prompt, mut text) = match self.input.take() {
+            Some(input) => input,
+            None => return,
+        };
+        match key.code {
+            KeyCode::Esc => return,
+            KeyCode::Enter => {
+                if let Err(e) = self.apply_input(prompt, &text) {
+                    self.message = Some(e);
+                }
+                return;
+            }
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) => text.push(c),
+            _ => {}
+        }
+        self.input = Some((prompt, text));
+    }
+
+    fn apply_input(&mut self, prompt: Prompt, text: &str) -> Result<(), String> {
+        if let Prompt::Signal(target) = prompt {
+            let signal = control::parse_signal(text)?;
+            let keys = match target {
+                SignalTarget::Selected => self.selected_key().into_iter().collect(),
+                SignalTarget::Shown => self.sys.filtered_processes().iter().map(|p| p.key()).collect(),
+            };
+            self.confirm = Some((signal, keys));
+            return Ok(());
+        }
+        if prompt == Prompt::Nice {
+            let nice = control::parse_nice(text)?;
+            let tasks = self.selected_tasks();
+            let errors = tasks
+                .iter()
+                .filter_map(|key| control::set_priority_of(self.sys.root(), *key, nice).err())
+                .collect();
+            self.report(format!("Set the nice value to {}", nice), errors);
+            return Ok(());
+        }
+        if prompt == Prompt::Affinity {
+            let cpus = linux_parser::parse_cpu_list(text)?;
+            if cpus.is_empty() {
+                return Err(String::from("The CPU list is empty"));
+            }
+            let tasks = self.selected_tasks();
+            let errors = tasks
+                .iter()
+                .filter_map(|key| control::set_affinity_of(self.sys.root(), *key, &cpus).err())
+                .collect();
+            self.report(format!("Pinned to CPUs {}", text), errors);
+            return Ok(());
+        }
+
+        let mut filter = self.sys.filter().clone();
+        match prompt {
+            Prompt::User => filter.users = filter::parse_list(text),
+            Prompt::Pid => filter.pids = filter::parse_pids(text)?,
+            Prompt::Command if text.is_empty() => filter.command = None,
+            Prompt::Command => filter.command = Some(CommandPattern::Substring(text.to_string())),
+            Prompt::CommandRegex if text.is_empty() => filter.command = None,
+            Prompt::CommandRegex => filter.command = Some(CommandPattern::regex(text)?),
+            Prompt::Container => filter.containers = filter::parse_list(text),
+            Prompt::Signal(_) | Prompt::Nice | Prompt::Affinity => {}
+        }
+        self.sys.set_filter(filter);
+        self.filter_changed();
+        Ok(())
+    }
+
+    // the processes may have exited and their pids been reused while the question was open
+    fn send_signal(&mut self, signal: Signal, keys: &[(u32, u64)]) {
+        let errors = control::send_signal_all(self.sys.root(), keys, signal);
+        self.report(format!("Sent {} to {} process(es)", signal, keys.len()), errors);
+    }
+
+    // the (pid, start time) of the selected process, or of the selected thread in the thread view
+    fn selected_key(&self) -> Option<(u32, u64)> {
+        let pid = self.selected_pid?;
+        if self.view == View::Threads {
+            return self.sys.threads().iter().find(|t| t.tid() == pid).map(Thread::key);
+        }
+        self.sys.processes().iter().find(|p| p.pid() == pid).map(Process::key)
+    }
+
+    fn foreign_pids_message(&self, action: &str) -> String {
+        format!("Can't {}, the pids of {} belong to another pid namespace", action, self.sys.root().proc_dir())
+    }
+
+    // the (tid, start time) keys of the threads a nice value or affinity applies to:
+    // the selected one in the thread view, every thread of the selected process otherwise
+    fn selected_tasks(&self) -> Vec<(u32, u64)> {
+        let key = match self.selected_key() {
+            Some(key) => key,
+            None => return vec![],
+        };
+        if self.view == View::Threads {
+            return vec![key];
+        }
+        control::thread_keys(self.sys.root(), key)
+    }
+
+    // shows `done` if there were no errors, the first error otherwise
+    fn report(&mut self, done: String, errors: Vec<ControlError>) {
+        self.message = Some(match errors.first() {
+            None => done,
+            Some(e) if errors.len() == 1 => e.to_string(),
+            Some(e) => format!("{} (and {} more failed)", e, errors.len() - 1),
+        });
+    }
+
+    // what the confirmation asks about, with the command if it's a single process
+    fn confirm_question(&self, signal: Signal, keys: &[(u32, u64)]) -> String {
+        match keys {
+            [(pid, _)] => {
+                let command = self.sys
+                    .processes()
+                    .iter()
+                    .find(|p| p.pid() == *pid)
+                    .map(|p| p.command().split('\0').next().unwrap_or("").to_string())
+                    .unwrap_or_default();
+                format!(" Send {} to {} {}? (y/n)", signal, pid, command)
+            }
+            _ => format!(" Send {} to {} processes? (y/n)", signal, keys.len()),
+        }
+    }
+
+    fn change_delay(&mut self, step_millis: i64) {
+        let millis = self.delay.as_millis() as i64 + step_millis;
+        let millis = (millis.max(0) as u64).clamp(MIN_DELAY_MILLIS, MAX_DELAY_MILLIS);
+        self.delay = Duration::from_millis(millis);
+    }
+
+    fn status_line(&self) -> String {
+        if let Some((prompt, text)) = &self.input {
+            return format!(" {}: {}_", prompt.label(), text);
+        }
+        if let Some((signal, keys)) = &self.confirm {
+            return self.confirm_question(*signal, keys);
+        }
+        if let Some(message) = &self.message {
+            return format!(" {}", message);
+        }
+        let (key, order) = self.sys.sort();
+        let views = match (self.view, self.sys.thread_scope()) {
+            (View::Fds(pid), _) => format!("  open files of {}, Esc back", pid),
+            (_, ThreadScope::Process(pid)) => format!("  threads of {}, Esc back", pid),
+            (_, ThreadScope::All) => String::from("  all threads, Esc back"),
+            (_, ThreadScope::None) => String::from("  T threads  H all threads  f open files"),
+        };
+        format!(
+            " q quit  p pause  \u{2191}\u{2193} scroll  </> sort: {} {}  r reverse  u # / \\ c filter  x clear  K/A signal  n nice  a affinity  l loopback  t tree  g cgroups  L listening{}  +/- delay: {:.1}s{}",
+            key.label(),
+            match order {
+                SortOrder::Ascending => "asc",
+                SortOrder::Descending => "desc",
+            },
+            views,
+            self.delay.as_secs_f64(),
+            if self.paused { "  [PAUSED]" } else { "" },
+        )
+    }
+
+    fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let (width, height) = term::size();
+        let width = width as usize;
+        let summary = self.sys.summary();
+        let summary: Vec<&str> = summary.lines().collect();
+
+        // summary, blank line, table header and status line
+        self.page_size = (height as usize).saturating_sub(summary.len() + 3);
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + self.page_size {
+            self.offset = self.selected + 1 - self.page_size.max(1);
+        }
+
+        // (text, reverse, alert)
+        let mut lines: Vec<(String, bool, bool)> = summary
+            .iter()
+            .map(|text| (text.to_string(), false, false))
+            .collect();
+        lines.push((String::new(), false, false));
+        lines.push((self.table_head(), true, false));
+        let rows = self.rows();
+        for (i, (_, text, alert)) in rows.into_iter().enumerate().skip(self.offset).take(self.page_size) {
+            lines.push((text, i == self.selected, alert));
+        }
+
+        for (row, (text, reverse, alert)) in lines.iter().enumerate() {
+            write_line(out, row as u16, text, width, *reverse, *alert)?;
+        }
+        queue!(out, terminal::Clear(terminal::ClearType::FromCursorDown))?;
+        write_line(out, height.saturating_sub(1), &self.status_line(), width, true, false)?;
+        out.flush()
+    }
+}
+
+// one row of the screen, cut to the terminal width, red if it needs attention
+fn write_line(out: &mut impl Write, row: u16, text: &str, width: usize, reverse: bool, alert: bool) -> io::Result<()> {
+    let text: String = text.chars().take(width).collect();
+    queue!(out, cursor::MoveTo(0, row))?;
+    if alert {
+        queue!(out, style::SetForegroundColor(style::Color::Red))?;
+    }
+    if reverse {
+        queue!(
+            out,
+            style::SetAttribute(style::Attribute::Reverse),
+            style::Print(format!("{:<width$}", text, width = width)),
+            style::SetAttribute(style::Attribute::Reset),
+        )?;
+    } else {
+        queue!(out, style::Print(text), terminal::Clear(terminal::ClearType::UntilNewLine))?;
+    }
+    if alert {
+        queue!(out, style::ResetColor)?;
+    }
+    Ok(())
+}
+
+// the view picked on the command line
+fn start_view(config: &Config) -> View {
+    if config.listening {
+        View::Listening
+    } else if config.cgroups {
+        View::Cgroups
+    } else if config.tree {
+        View::Tree
+    } else {
+        View::List
+    }
+}
+
+pub fn run(sys: System, delay_millis: u64, config: &Config) -> Result<(), Box<dyn Error>> {
+    let mut app = App::new(sys, delay_millis, start_view(config));
+    app.select(0);
+
+    let _screen = Screen::enter()?;
+    let mut out = io::stdout();
+    let mut last_refresh = Instant::now();
+    loop {
+        app.draw(&mut out)?;
+
+        let timeout = app.delay.saturating_sub(last_refresh.elapsed());
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key);
+                }
+            }
+            if app.quit {
+                return Ok(());
+            }
+        }
+
+        if last_refresh.elapsed() >= app.delay {
+            if !app.paused {
+                // keep showing the last snapshot, the next refresh may work again
+                if let Err(e) = app.refresh() {
+                    app.message = Some(e.to_string());
+                }
+            }
+            last_refresh = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use process_monitor::{ContainerNames, SortKey};
+    use crate::batch::tests::fixture_system;
+
+    fn fixture_app() -> App {
+        let mut app = App::new(fixture_system(), 2000, View::List);
+        app.select(0);
+        app
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn scroll_stays_in_bounds() {
+        let mut app = fixture_app();
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.selected, 0);
+        press(&mut app, KeyCode::End);
+        assert_eq!(app.selected, 2);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected, 2);
+    }
+
+    #[test]
+    fn selection_follows_pid_when_sorting() {
+        let mut app = fixture_app();
+        app.sys.set_sort(SortKey::Pid, SortOrder::Ascending);
+        press(&mut app, KeyCode::End);
+        assert_eq!(app.selected_pid, Some(42));
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(app.sys.sort(), (SortKey::Pid, SortOrder::Descending));
+        assert_eq!(app.selected, 0);
+        assert_eq!(app.pids()[app.selected], 42);
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn filter_from_prompt() {
+        let mut app = fixture_app();
+        press(&mut app, KeyCode::Char('u'));
+        type_text(&mut app, "alice");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.pids(), vec![42]);
+        assert_eq!(app.selected_pid, Some(42));
+
+        press(&mut app, KeyCode::Char('x'));
+        assert_eq!(app.pids().len(), 3);
+    }
+
+    #[test]
+    fn collapse_in_tree_view() {
+        let mut app = fixture_app();
+        app.sys.set_sort(SortKey::Pid, SortOrder::Ascending);
+        press(&mut app, KeyCode::Char('t'));
+        assert_eq!(app.pids(), vec![1, 42, 2]);
+
+        press(&mut app, KeyCode::Home);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.pids(), vec![1, 2]);
+        press(&mut app, KeyCode::Right);
+        assert_eq!(app.pids(), vec![1, 42, 2]);
+    }
+
+    #[test]
+    fn filter_by_container_name() {
+        let mut app = fixture_app();
+        let names = ContainerNames::from_json(r#"{"3f4e1a2b5c6d": "worker"}"#).unwrap();
+        app.sys.set_container_resolver(Box::new(names));
+        press(&mut app, KeyCode::Char('c'));
+        type_text(&mut app, "worker");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.pids(), vec![42]);
+        assert!(app.rows()[0].1.contains(" worker "));
+    }
+
+    #[test]
+    fn invalid_filter_shows_message() {
+        let mut app = fixture_app();
+        press(&mut app, KeyCode::Char('\\'));
+        type_text(&mut app, "(");
+        press(&mut app, KeyCode::Enter);
+        assert!(app.message.is_some());
+        assert!(app.sys.filter().is_empty());
+    }
+
+    #[test]
+    fn escape_cancels_prompt() {
+        let mut app = fixture_app();
+        press(&mut app, KeyCode::Char('/'));
+        type_text(&mut app, "python");
+        press(&mut app, KeyCode::Esc);
+        assert!(!app.quit);
+        assert!(app.sys.filter().is_empty());
+    }
+
+    #[test]
+    fn delay_is_clamped() {
+        let mut app = fixture_app();
+        for _ in 0..10 {
+            press(&mut app, KeyCode::Char('-'));
+        }
+        assert_eq!(app.delay, Duration::from_millis(MIN_DELAY_MILLIS));
+    }
+
+    #[test]
+    fn threads_of_selected_process() {
+        let mut app = fixture_app();
+        app.sys.set_sort(SortKey::Pid, SortOrder::Ascending);
+        press(&mut app, KeyCode::End);
+        press(&mut app, KeyCode::Char('T'));
+        assert_eq!(app.sys.thread_scope(), ThreadScope::Process(42));
+        assert_eq!(app.pids(), vec![42, 43]);
+
+        press(&mut app, KeyCode::End);
+        press(&mut app, KeyCode::Esc);
+        assert!(!app.quit);
+        assert_eq!(app.sys.thread_scope(), ThreadScope::None);
+        assert_eq!(app.selected_pid, Some(42));
+    }
+
+    #[test]
+    fn all_threads_follow_filter() {
+        let mut app = fixture_app();
+        press(&mut app, KeyCode::Char('H'));
+        assert_eq!(app.pids().len(), 4);
+
+        press(&mut app, KeyCode::Char('u'));
+        type_text(&mut app, "root");
+        press(&mut app, KeyCode::Enter);
+        let mut tids = app.pids();
+        tids.sort_unstable();
+        assert_eq!(tids, vec![1, 2]);
+
+        press(&mut app, KeyCode::Char('H'));
+        assert_eq!(app.view, View::List);
+    }
+
+    #[test]
+    fn signal_needs_confirmation() {
+        let mut app = fixture_app();
+        // the fixture has no self link, treat it as our own pid namespace to get to the question
+        app.local_pids = true;
+        app.sys.set_sort(SortKey::Pid, SortOrder::Ascending);
+        press(&mut app, KeyCode::End);
+        press(&mut app, KeyCode::Char('K'));
+        for _ in 0..4 {
+            press(&mut app, KeyCode::Backspace);
+        }
+        type_text(&mut app, "stop");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.confirm, Some((Signal::SIGSTOP, vec![(42, 50000)])));
+        assert!(app.status_line().contains("SIGSTOP to 42 python3"));
+
+        // anything but y cancels, the fixture pids must never be signaled
+        press(&mut app, KeyCode::Char('n'));
+        assert!(app.confirm.is_none());
+        assert_eq!(app.message.as_deref(), Some("Cancelled"));
+    }
+
+    #[test]
+    fn signal_shown_processes() {
+        let mut app = fixture_app();
+        app.local_pids = true;
+        press(&mut app, KeyCode::Char('A'));
+        assert!(app.input.is_none());
+        assert!(app.message.is_some());
+
+        press(&mut app, KeyCode::Char('u'));
+        type_text(&mut app, "root");
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('A'));
+        press(&mut app, KeyCode::Enter);
+        let (signal, mut keys) = app.confirm.clone().unwrap();
+        keys.sort_unstable();
+        assert_eq!((signal, keys), (Signal::SIGTERM, vec![(1, 10), (2, 10)]));
+        press(&mut app, KeyCode::Esc);
+        assert!(app.confirm.is_none());
+        assert!(!app.quit);
+    }
+
+    #[test]
+    fn foreign_pids_are_not_signaled() {
+        let mut app = fixture_app();
+        press(&mut app, KeyCode::Char('K'));
+        assert!(app.input.is_none());
+        assert!(app.message.as_deref().unwrap().contains("another pid namespace"));
+    }
+
+    #[test]
+    fn unknown_signal_shows_message() {
+        let mut app = fixture_app();
+        app.local_pids = true;
+        press(&mut app, KeyCode::Char('K'));
+        type_text(&mut app, "X");
+        press(&mut app, KeyCode::Enter);
+        assert!(app.confirm.is_none());
+        assert_eq!(app.message.as_deref(), Some("Unknown signal TERMX"));
+    }
+
+    #[test]
+    fn renice_prompt_starts_with_current_value() {
+        let mut app = fixture_app();
+        app.local_pids = true;
+        app.sys.set_sort(SortKey::Pid, SortOrder::Ascending);
+        press(&mut app, KeyCode::End);
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.input, Some((Prompt::Nice, String::from("10"))));
+        type_text(&mut app, "0");
+        press(&mut app, KeyCode::Enter);
+        assert!(app.message.as_deref().unwrap().starts_with("Invalid nice value 100"));
+
+        press(&mut app, KeyCode::Char('a'));
+        assert_eq!(app.input, Some((Prompt::Affinity, String::from("0-1"))));
+        for _ in 0..3 {
+            press(&mut app, KeyCode::Backspace);
+        }
+        // only invalid values, the fixture pids may exist on the host
+        type_text(&mut app, "3-1");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.message.as_deref(), Some("Invalid CPU list 3-1"));
+
+        // the worker thread is pinned to CPU 1, unlike its process
+        press(&mut app, KeyCode::Char('T'));
+        press(&mut app, KeyCode::End);
+        press(&mut app, KeyCode::Char('a'));
+        assert_eq!(app.input, Some((Prompt::Affinity, String::from("1"))));
+    }
+
+    #[test]
+    fn foreign_pids_are_not_reniced_or_pinned() {
+        let mut app = fixture_app();
+        press(&mut app, KeyCode::Char('n'));
+        assert!(app.input.is_none());
+        assert!(app.message.as_deref().unwrap().starts_with("Can't renice"));
+        press(&mut app, KeyCode::Char('a'));
+        assert!(app.input.is_none());
+        assert!(app.message.as_deref().unwrap().starts_with("Can't change the CPU affinity"));
+    }
+
+    #[test]
+    fn toggle_loopback() {
+        let mut app = fixture_app();
+        assert!(app.sys.summary().contains("- lo "));
+        press(&mut app, KeyCode::Char('l'));
+        assert!(!app.sys.summary().contains("- lo "));
+    }
+
+    #[test]
+    fn cgroup_view() {
+        let mut app = fixture_app();
+        app.local_pids = true;
+        app.sys.set_sort(SortKey::Command, SortOrder::Ascending);
+        press(&mut app, KeyCode::Char('t'));
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(app.view, View::Cgroups);
+        assert_eq!(app.pids(), vec![0, 1, 2]);
+        assert!(app.rows()[2].1.contains("/system.slice/docker-3f4e1a2b5c6d"));
+
+        // the rows aren't processes
+        press(&mut app, KeyCode::Char('K'));
+        press(&mut app, KeyCode::Char('n'));
+        press(&mut app, KeyCode::Char('T'));
+        assert!(app.input.is_none());
+        assert_eq!(app.sys.thread_scope(), ThreadScope::None);
+
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(app.view, View::Tree);
+    }
+
+    #[test]
+    fn open_files_of_selected_process() {
+        let mut app = fixture_app();
+        app.sys.set_sort(SortKey::Pid, SortOrder::Ascending);
+        let alerts: Vec<bool> = app.rows().iter().map(|(_, _, alert)| *alert).collect();
+        assert_eq!(alerts, vec![false, false, true]);
+
+        press(&mut app, KeyCode::End);
+        press(&mut app, KeyCode::Char('f'));
+        assert_eq!(app.view, View::Fds(42));
+        assert_eq!(app.pids(), vec![0, 1, 2, 3, 4, 5]);
+        assert!(app.rows()[3].1.contains("socket:[2001]"));
+        // fds can't be signaled
+        press(&mut app, KeyCode::Char('K'));
+        assert!(app.input.is_none());
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.view, View::List);
+        assert_eq!(app.selected_pid, Some(42));
+
+        // pid 1 has no fd directory
+        press(&mut app, KeyCode::Home);
+        press(&mut app, KeyCode::Char('f'));
+        assert_eq!(app.view, View::List);
+        assert!(app.message.is_some());
+    }
+
+    #[test]
+    fn listening_view() {
+        let mut app = fixture_app();
+        press(&mut app, KeyCode::Char('g'));
+        press(&mut app, KeyCode::Char('L'));
+        assert_eq!(app.view, View::Listening);
+        let rows = app.rows();
+        assert!(rows[0].1.starts_with("tcp6  LISTEN     *:22"));
+        assert!(rows[3].1.contains("*:8080"));
+        assert!(rows[3].1.contains("python3 worker.py"));
+        // sockets can't be signaled
+        press(&mut app, KeyCode::Char('K'));
+        assert!(app.input.is_none());
+
+        press(&mut app, KeyCode::Char('L'));
+        assert_eq!(app.view, View::List);
+    }
+
+    #[test]
+    fn quit_on_ctrl_c() {
+        let mut app = fixture_app();
+        app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(app.quit);
+    }
+}
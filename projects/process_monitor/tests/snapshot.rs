@@ -0,0 +1,80 @@
+use process_monitor::{FsRoot, ProcessFilter, SortKey, SortOrder, System, ThreadScope};
+
+fn fixture_root() -> FsRoot {
+    FsRoot::new(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/proc"),
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/etc"),
+    )
+}
+
+#[test]
+fn snapshot_from_fixtures() {
+    let mut sys = System::new(fixture_root()).unwrap();
+    sys.refresh().unwrap();
+
+    assert_eq!(sys.os(), "Fixture Linux 1.0");
+    assert_eq!(sys.kernel(), "5.15.0-fixture");
+    assert_eq!(sys.cpu().get_cpu_count(), 2);
+    assert_eq!(sys.memory().used(), 3000000);
+    assert_eq!(sys.processes().len(), 3);
+
+    let worker = sys.processes().iter().find(|p| p.pid() == 42).unwrap();
+    assert_eq!(worker.user(), "alice");
+    assert_eq!(worker.command(), "python3\0worker.py");
+}
+
+#[test]
+fn sort_survives_refresh() {
+    let mut sys = System::new(fixture_root()).unwrap();
+    sys.set_sort(SortKey::Pid, SortOrder::Descending);
+    sys.refresh().unwrap();
+
+    let pids: Vec<u32> = sys.processes().iter().map(|p| p.pid()).collect();
+    assert_eq!(pids, vec![42, 2, 1]);
+}
+
+#[test]
+fn threads_of_filtered_processes() {
+    let mut sys = System::new(fixture_root()).unwrap();
+    sys.set_filter(ProcessFilter { users: vec![String::from("alice")], ..Default::default() });
+    sys.set_thread_scope(ThreadScope::All);
+    sys.set_sort(SortKey::Pid, SortOrder::Ascending);
+    sys.refresh().unwrap();
+
+    let threads: Vec<(u32, &str)> = sys.threads().iter().map(|t| (t.tid(), t.name())).collect();
+    assert_eq!(threads, vec![(42, "python3"), (43, "worker-0")]);
+    assert!(sys.threads().iter().all(|t| t.pid() == 42));
+
+    let worker = sys.processes().iter().find(|p| p.pid() == 42).unwrap();
+    assert_eq!(worker.threads(), 2);
+}
+
+#[test]
+fn load_and_forks() {
+    let mut sys = System::new(fixture_root()).unwrap();
+    // the fixture's 5000 forks over 1000.5 seconds since boot
+    assert!((sys.forks_per_second() - 5000.0 / 1000.5).abs() < 1e-9);
+    sys.refresh().unwrap();
+    // nothing changed between the two samples
+    assert_eq!(sys.forks_per_second(), 0.0);
+    assert_eq!(sys.total_processes(), 3);
+    assert_eq!(sys.load_avg().five, 0.75);
+    assert!(sys.summary().contains("Load average: 0.50, 0.75, 1.25  Tasks: 2 runnable, 345 total"));
+}
+
+#[test]
+fn listening_ports_and_connections() {
+    let mut sys = System::new(fixture_root()).unwrap();
+    sys.set_sort(SortKey::Connections, SortOrder::Descending);
+    sys.refresh().unwrap();
+    assert_eq!(sys.processes()[0].pid(), 42);
+    assert_eq!(sys.processes()[0].connections(), Some(1));
+
+    let http = sys.listening_sockets().into_iter().find(|row| row.socket.port == Some(8080)).unwrap();
+    assert_eq!(http.process.map(|p| p.pid()), Some(42));
+    assert!(http.to_string().starts_with("tcp   LISTEN     *:8080"));
+
+    // with a filter only the sockets of the shown processes are listed
+    sys.set_filter(ProcessFilter { users: vec![String::from("root")], ..Default::default() });
+    assert!(sys.listening_sockets().is_empty());
+}